@@ -8,10 +8,9 @@ async fn main() -> Result<()> {
 
     let list = client.lang.list().await?;
     let router = list.routers.first().unwrap();
-    let name = router["routerID"].as_str().unwrap();
 
     let request = ChatRequest::new("Hello!");
-    let response = client.lang.chat(&name, request).await?;
+    let response = client.lang.chat(&router.id, request).await?;
     println!("response: {}", response.content());
 
     Ok(())
@@ -1,7 +1,14 @@
+use std::sync::Arc;
 use std::{env, fmt};
 
 use reqwest::{Client as RwClient, Url};
 
+use crate::lang::budget::Budgets;
+use crate::lang::cost::CostTracker;
+#[cfg(feature = "runtime")]
+use crate::lang::discovery::RouterCache;
+use crate::lang::truncate::TruncationPolicy;
+use crate::types::Version;
 use crate::{Client, Config};
 
 /// [`Client`] builder.
@@ -11,6 +18,13 @@ pub struct Builder {
     base_url: Option<Url>,
     user_agent: Option<String>,
     http_client: Option<RwClient>,
+    truncation: Option<Arc<dyn TruncationPolicy>>,
+    router_truncation: Vec<(String, Arc<dyn TruncationPolicy>)>,
+    cost: Option<CostTracker>,
+    budgets: Option<Budgets>,
+    #[cfg(feature = "runtime")]
+    routers: Option<RouterCache>,
+    gateway_version: Option<Version>,
 }
 
 impl Builder {
@@ -23,6 +37,13 @@ impl Builder {
             base_url: None,
             user_agent: None,
             http_client: None,
+            truncation: None,
+            router_truncation: Vec::new(),
+            cost: None,
+            budgets: None,
+            #[cfg(feature = "runtime")]
+            routers: None,
+            gateway_version: None,
         }
     }
 
@@ -56,6 +77,67 @@ impl Builder {
         self
     }
 
+    /// Attaches the default [`TruncationPolicy`] applied to all `router`s.
+    ///
+    /// Default value: `None`
+    pub fn with_truncation(mut self, policy: impl TruncationPolicy + 'static) -> Self {
+        self.truncation = Some(Arc::new(policy));
+        self
+    }
+
+    /// Attaches the [`TruncationPolicy`] applied to the specified `router`.
+    ///
+    /// Takes precedence over [`Builder::with_truncation`].
+    pub fn with_router_truncation(
+        mut self,
+        router: &str,
+        policy: impl TruncationPolicy + 'static,
+    ) -> Self {
+        let policy: Arc<dyn TruncationPolicy> = Arc::new(policy);
+        self.router_truncation.push((router.to_owned(), policy));
+        self
+    }
+
+    /// Attaches the [`CostTracker`] recording all chat responses.
+    ///
+    /// Default value: `None`
+    pub fn with_cost_tracker(mut self, tracker: CostTracker) -> Self {
+        self.cost = Some(tracker);
+        self
+    }
+
+    /// Attaches the [`Budgets`] enforced on all chat requests.
+    ///
+    /// Default value: `None`
+    pub fn with_budgets(mut self, budgets: Budgets) -> Self {
+        self.budgets = Some(budgets);
+        self
+    }
+
+    /// Attaches the [`RouterCache`] used to validate `router` names before sending.
+    ///
+    /// Default value: `None`
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+    pub fn with_router_cache(mut self, cache: RouterCache) -> Self {
+        self.routers = Some(cache);
+        self
+    }
+
+    /// Declares the [`Version`] of the connected gateway, as it does not report one.
+    ///
+    /// Requests that need a [`Capability`] the gateway lacks then fail
+    /// with [`Error::Unsupported`] without being sent.
+    ///
+    /// Default value: `None`, the gateway is assumed to support everything.
+    ///
+    /// [`Capability`]: crate::types::Capability
+    /// [`Error::Unsupported`]: crate::Error::Unsupported
+    pub const fn with_gateway_version(mut self, version: Version) -> Self {
+        self.gateway_version = Some(version);
+        self
+    }
+
     /// Creates a new [`Client`].
     ///
     /// ### Panics
@@ -69,6 +151,13 @@ impl Builder {
             user_agent: self.user_agent.unwrap_or_else(default_user_agent),
             base_url: self.base_url.unwrap_or_else(default_base_url),
             client: self.http_client.unwrap_or_default(),
+            truncation: self.truncation,
+            router_truncation: self.router_truncation.into_iter().collect(),
+            cost: self.cost,
+            budgets: self.budgets,
+            #[cfg(feature = "runtime")]
+            routers: self.routers,
+            gateway_version: self.gateway_version,
         };
 
         config.into_client()
@@ -84,11 +173,18 @@ impl Default for Builder {
 
 impl fmt::Debug for Builder {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Builder")
-            .field("user_agent", &self.user_agent.is_some())
+        let mut f = f.debug_struct("Builder");
+        f.field("user_agent", &self.user_agent.is_some())
             .field("base_url", &self.base_url.is_some())
             .field("http_client", &self.http_client.is_some())
-            .finish_non_exhaustive()
+            .field("truncation", &self.truncation)
+            .field("router_truncation", &self.router_truncation)
+            .field("cost", &self.cost.is_some())
+            .field("budgets", &self.budgets)
+            .field("gateway_version", &self.gateway_version);
+        #[cfg(feature = "runtime")]
+        f.field("routers", &self.routers);
+        f.finish_non_exhaustive()
     }
 }
 
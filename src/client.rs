@@ -1,9 +1,17 @@
 use std::fmt;
 use std::sync::Arc;
+#[cfg(feature = "runtime")]
+use std::time::Duration;
 
-use reqwest::{Client as RwClient, Method};
+use reqwest::Client as RwClient;
 
+use crate::lang::cost::CostTracker;
+#[cfg(feature = "runtime")]
+use crate::lang::discovery::RouterCache;
 use crate::lang::Language;
+#[cfg(feature = "runtime")]
+use crate::types::HealthWatch;
+use crate::types::{Capability, HealthReport, Version};
 use crate::{Builder, Config, Result};
 
 /// A minimal [EinStack](https://einstack.ai/) client.
@@ -17,6 +25,7 @@ use crate::{Builder, Config, Result};
 /// let glide = Client::default();
 /// glide.health().await?;
 /// let _ = glide.lang.list().await?;
+/// # Ok::<_, glide_rs::Error>(())
 /// # };
 /// ```
 #[must_use]
@@ -72,6 +81,22 @@ impl Client {
         &self.config.client
     }
 
+    /// Returns the attached [`CostTracker`], if any.
+    #[inline]
+    #[must_use]
+    pub fn cost_tracker(&self) -> Option<&CostTracker> {
+        self.config.cost.as_ref()
+    }
+
+    /// Returns the attached [`RouterCache`], if any.
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+    #[inline]
+    #[must_use]
+    pub fn router_cache(&self) -> Option<&RouterCache> {
+        self.config.routers.as_ref()
+    }
+
     /// Returns `true` if the service is healthy.
     ///
     /// `GET /v1/health`
@@ -83,16 +108,50 @@ impl Client {
     /// [`Error`]: crate::Error
     /// [`StatusCode`]: reqwest::StatusCode
     pub async fn health(&self) -> Result<bool> {
-        #[derive(Debug, serde::Deserialize)]
-        pub struct Health {
-            pub healthy: bool,
-        }
+        let report = self.health_report().await?;
+        Ok(report.healthy)
+    }
 
-        let request = self.config.create(Method::GET, "/v1/health/");
-        let response = self.config.send(request).await?;
-        let content = response.json::<Health>().await?;
+    /// Retrieves the full health payload and measures its latency.
+    ///
+    /// `GET /v1/health`
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the response [`StatusCode`] is not in the 200-299 range.
+    ///
+    /// [`Error`]: crate::Error
+    /// [`StatusCode`]: reqwest::StatusCode
+    pub async fn health_report(&self) -> Result<HealthReport> {
+        self.config.health_report().await
+    }
+
+    /// Returns the gateway [`Version`] declared with [`Builder::with_gateway_version`], if any.
+    #[inline]
+    #[must_use]
+    pub fn gateway_version(&self) -> Option<Version> {
+        self.config.gateway_version
+    }
 
-        Ok(content.healthy)
+    /// Returns `true` if the gateway has the [`Capability`].
+    ///
+    /// Gateways of unknown version are assumed to support everything.
+    #[inline]
+    #[must_use]
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.config.supports(capability)
+    }
+
+    /// Creates a new [`HealthWatch`] stream, checking the health every `interval`
+    /// and yielding its transitions.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the `interval` is zero or called outside of the `tokio` runtime.
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+    pub fn watch_health(&self, interval: Duration) -> HealthWatch {
+        HealthWatch::new(self.clone(), interval)
     }
 }
 
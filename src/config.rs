@@ -1,17 +1,32 @@
+use std::collections::HashMap;
 use std::fmt;
 use std::sync::Arc;
+use std::time::Instant;
 
 use reqwest::header::USER_AGENT;
 use reqwest::{Client as RwClient, Method, RequestBuilder, Response, Url};
 
+use crate::lang::budget::Budgets;
+use crate::lang::cost::CostTracker;
+#[cfg(feature = "runtime")]
+use crate::lang::discovery::RouterCache;
+use crate::lang::truncate::TruncationPolicy;
 use crate::lang::Language;
-use crate::{types::ErrorResponse, Client, Error, Result};
+use crate::types::{Capability, ErrorResponse, HealthReport, Unsupported, Version};
+use crate::{Client, Error, Result};
 
 pub struct Config {
     pub api_key: Option<String>,
     pub user_agent: String,
     pub base_url: Url,
     pub client: RwClient,
+    pub truncation: Option<Arc<dyn TruncationPolicy>>,
+    pub router_truncation: HashMap<String, Arc<dyn TruncationPolicy>>,
+    pub cost: Option<CostTracker>,
+    pub budgets: Option<Budgets>,
+    #[cfg(feature = "runtime")]
+    pub routers: Option<RouterCache>,
+    pub gateway_version: Option<Version>,
 }
 
 impl Config {
@@ -41,14 +56,59 @@ impl Config {
 
         match response.status() {
             x if x.is_client_error() || x.is_server_error() => {
-                let mut error = response.json::<ErrorResponse>().await?;
-                error.status = x;
-                Err(Error::Api(error))
+                Err(Error::Api(Self::error(response).await?))
             }
             _ => Ok(response),
         }
     }
 
+    /// Parses the [`ErrorResponse`] of the failed [`Response`].
+    pub async fn error(response: Response) -> Result<ErrorResponse> {
+        let status = response.status();
+        let mut error = response.json::<ErrorResponse>().await?;
+        error.status = status;
+        Ok(error)
+    }
+
+    /// Retrieves the [`HealthReport`] of the gateway.
+    pub async fn health_report(&self) -> Result<HealthReport> {
+        let started = Instant::now();
+        let request = self.create(Method::GET, "/v1/health/");
+        let response = self.send(request).await?;
+        let mut content = response.json::<HealthReport>().await?;
+        content.latency = started.elapsed();
+
+        Ok(content)
+    }
+
+    /// Returns `true` if the declared gateway version, if any, has the [`Capability`].
+    pub fn supports(&self, capability: Capability) -> bool {
+        let version = self.gateway_version;
+        version.is_none_or(|x| x >= capability.since())
+    }
+
+    /// Returns an [`Unsupported`] error if the declared gateway version lacks the [`Capability`].
+    pub fn require(&self, capability: Capability) -> Result<()> {
+        match self.supports(capability) {
+            true => Ok(()),
+            false => Err(self.unsupported(capability)),
+        }
+    }
+
+    /// Creates a new [`Unsupported`] error.
+    pub fn unsupported(&self, capability: Capability) -> Error {
+        Error::Unsupported(Unsupported {
+            capability,
+            version: self.gateway_version,
+        })
+    }
+
+    /// Returns the [`TruncationPolicy`] of the specified `router`, if any.
+    pub fn truncation(&self, router: &str) -> Option<&dyn TruncationPolicy> {
+        let policy = self.router_truncation.get(router);
+        policy.or(self.truncation.as_ref()).map(AsRef::as_ref)
+    }
+
     /// Creates a new [`Client`].
     pub fn into_client(self) -> Client {
         let config = Arc::new(self);
@@ -0,0 +1,60 @@
+//! `Go`-style duration parsing, e.g. `1h30m`, `1.5s` or `300ms`.
+//!
+
+use std::time::Duration;
+
+/// Parses the `Go`-style duration string.
+///
+/// Returns `None` if the string is malformed or negative.
+pub fn parse(input: &str) -> Option<Duration> {
+    let mut rest = input.trim();
+    if rest == "0" {
+        return Some(Duration::ZERO);
+    }
+
+    if rest.is_empty() {
+        return None;
+    }
+
+    let mut total = 0.0;
+    while !rest.is_empty() {
+        let split = rest.find(|x: char| !x.is_ascii_digit() && x != '.')?;
+        let (value, tail) = rest.split_at(split);
+        let value: f64 = value.parse().ok()?;
+
+        let split = tail.find(|x: char| x.is_ascii_digit() || x == '.');
+        let (unit, tail) = tail.split_at(split.unwrap_or(tail.len()));
+        let nanos = match unit {
+            "ns" => 1.0,
+            "us" | "µs" => 1e3,
+            "ms" => 1e6,
+            "s" => 1e9,
+            "m" => 60e9,
+            "h" => 3600e9,
+            _ => return None,
+        };
+
+        total += value * nanos;
+        rest = tail;
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    (total < u64::MAX as f64).then(|| Duration::from_nanos(total.round() as u64))
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::duration::parse;
+
+    #[test]
+    fn parse_duration() {
+        assert_eq!(parse("0"), Some(Duration::ZERO));
+        assert_eq!(parse("300ms"), Some(Duration::from_millis(300)));
+        assert_eq!(parse("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse("1h2m3s"), Some(Duration::from_secs(3723)));
+        assert_eq!(parse("10"), None);
+        assert_eq!(parse("1d"), None);
+    }
+}
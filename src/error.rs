@@ -1,10 +1,11 @@
 use reqwest::StatusCode;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 /// List specifying general categories of [`ErrorResponse`]s.
 #[must_use]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ErrorKind {
     /// Error name is not a part of the implemented `API` spec.
     Unrecognized,
@@ -20,7 +21,7 @@ pub enum ErrorKind {
 }
 
 /// Errors that may occur during the processing of API request.
-#[derive(Debug, Error, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize, Deserialize)]
 #[error("{message}")]
 pub struct ErrorResponse {
     pub name: String,
@@ -46,3 +47,23 @@ impl ErrorResponse {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use reqwest::StatusCode;
+    use serde_json::json;
+
+    use crate::types::{ErrorKind, ErrorResponse};
+
+    #[test]
+    fn wire_format() {
+        let value = json!({ "name": "router_not_found", "message": "router not found" });
+        let response: ErrorResponse = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(response.kind(), ErrorKind::RouterNotFound);
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(serde_json::to_value(&response).unwrap(), value);
+
+        let kind = serde_json::to_value(ErrorKind::AllModelsUnavailable).unwrap();
+        assert_eq!(kind, json!("all_models_unavailable"));
+    }
+}
@@ -0,0 +1,131 @@
+//! Shared fixtures of unit tests.
+//!
+
+#[cfg(feature = "runtime")]
+use std::io::{Read, Write};
+#[cfg(feature = "runtime")]
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "runtime")]
+use std::sync::Arc;
+
+use serde_json::json;
+
+use crate::lang::chat::ChatResponse;
+#[cfg(feature = "streaming")]
+use crate::lang::chat_stream::ChatStreamMessage;
+#[cfg(feature = "runtime")]
+use crate::Client;
+
+/// Temporary directory, removed with its contents on drop.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Creates a new unique temporary directory.
+    pub fn new(name: &str) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let name = format!("glide-rs-{name}-{}-{id}", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        std::fs::create_dir_all(&path).unwrap();
+
+        Self { path }
+    }
+
+    /// Returns the path of the directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Returns the response of `gpt-4o` of `openai` to `myrouter`.
+pub fn response(prompt_tokens: u32, response_tokens: u32) -> ChatResponse {
+    serde_json::from_value(json!({
+        "id": "1",
+        "created_at": 0,
+        "provider_id": "openai",
+        "router_id": "myrouter",
+        "model_id": "openai",
+        "model_name": "gpt-4o",
+        "cached": false,
+        "model_response": {
+            "message": { "content": "Hi!", "role": "assistant" },
+            "token_count": {
+                "prompt_tokens": prompt_tokens,
+                "response_tokens": response_tokens,
+                "total_tokens": prompt_tokens + response_tokens,
+            },
+        },
+    }))
+    .unwrap()
+}
+
+/// Returns the streamed chunk of `gpt-4o` of `openai` to `myrouter`.
+///
+/// The `last` chunk completes the response and has the token usage.
+#[cfg(feature = "streaming")]
+pub fn message(id: &str, content: &str, last: bool) -> ChatStreamMessage {
+    let mut value = json!({
+        "id": id,
+        "created_at": 0,
+        "router_id": "myrouter",
+        "chunk": {
+            "model_id": "gpt",
+            "model_name": "gpt-4o",
+            "provider_id": "openai",
+            "model_response": { "message": { "role": "assistant", "content": content } },
+        },
+    });
+
+    if last {
+        value["chunk"]["finish_reason"] = json!("complete");
+        value["chunk"]["model_response"]["token_count"] =
+            json!({ "prompt_tokens": 3, "response_tokens": 2, "total_tokens": 5 });
+    }
+
+    serde_json::from_value(value).unwrap()
+}
+
+/// Spawns the server responding to every request with the `JSON` body.
+///
+/// Returns the [`Client`] of the server and the number of received requests.
+#[cfg(feature = "runtime")]
+pub fn serve(body: &'static str) -> (Client, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    let requests = Arc::new(AtomicUsize::new(0));
+
+    let counter = requests.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+
+            let mut buffer = [0; 4096];
+            let _ = stream.read(&mut buffer);
+            counter.fetch_add(1, Ordering::SeqCst);
+
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+
+    let client = Client::builder().with_base_url(url.parse().unwrap());
+    (client.build(), requests)
+}
@@ -0,0 +1,167 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Semantic version of the gateway, e.g. `0.1.0`.
+///
+/// Pre-release and build suffixes are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    /// Creates a new [`Version`].
+    #[inline]
+    #[must_use]
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("invalid version: {s}");
+        let version = s.trim().trim_start_matches('v');
+        let version = version.split(['-', '+']).next().unwrap_or_default();
+
+        let mut parts = version.split('.').map(str::parse::<u32>);
+        let mut next = || parts.next().unwrap_or(Ok(0)).map_err(|_| error());
+        let version = Self::new(next()?, next()?, next()?);
+        match parts.next() {
+            Some(_) => Err(error()),
+            None => Ok(version),
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Feature of the gateway that not all versions support.
+///
+/// Versions are taken from the [changelog] of the gateway.
+///
+/// [changelog]: https://github.com/EinStack/glide/blob/main/CHANGELOG.md
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// `WebSocket` chat streaming, `GET /v1/language/{router}/chatStream`.
+    ///
+    /// Since `0.0.3`.
+    Streaming,
+    /// Provider-specific model parameters of [`ChatRequestOverride`]s.
+    ///
+    /// Since `0.1.0`, overrides of only the message are supported by all versions.
+    ///
+    /// [`ChatRequestOverride`]: crate::lang::chat::ChatRequestOverride
+    Overrides,
+}
+
+impl Capability {
+    /// Returns the first gateway [`Version`] with the capability.
+    ///
+    /// Gateways of unknown version are assumed to support everything.
+    #[must_use]
+    pub const fn since(&self) -> Version {
+        match self {
+            Self::Streaming => Version::new(0, 0, 3),
+            Self::Overrides => Version::new(0, 1, 0),
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Streaming => f.write_str("streaming"),
+            Self::Overrides => f.write_str("overrides"),
+        }
+    }
+}
+
+/// Request that requires a [`Capability`] the connected gateway does not support.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+#[error("{capability} {}", Requires(self))]
+pub struct Unsupported {
+    pub capability: Capability,
+    /// Declared version of the gateway, if any.
+    pub version: Option<Version>,
+}
+
+struct Requires<'a>(&'a Unsupported);
+
+impl fmt::Display for Requires<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let since = self.0.capability.since();
+        match self.0.version {
+            Some(version) => write!(
+                f,
+                "requires gateway {since} or newer, connected to {version}"
+            ),
+            None => f.write_str("is not supported by the gateway"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::gateway::{Capability, Unsupported, Version};
+    use crate::lang::chat::{ChatRequest, ChatRequestOverride};
+    use crate::lang::params::OpenAiParams;
+    use crate::{Client, Error};
+
+    #[test]
+    fn version() {
+        assert_eq!("v0.1.2".parse(), Ok(Version::new(0, 1, 2)));
+        assert_eq!("0.1.0-rc.1".parse(), Ok(Version::new(0, 1, 0)));
+        assert_eq!("1.2".parse(), Ok(Version::new(1, 2, 0)));
+        assert!("0.1.x".parse::<Version>().is_err());
+        assert!("1.2.3.4".parse::<Version>().is_err());
+        assert!(Version::new(0, 0, 3) < Version::new(0, 1, 0));
+    }
+
+    #[test]
+    fn supports() {
+        let glide = Client::builder()
+            .with_gateway_version(Version::new(0, 0, 3))
+            .build();
+        assert!(glide.supports(Capability::Streaming));
+        assert!(!glide.supports(Capability::Overrides));
+        assert!(Client::default().supports(Capability::Overrides));
+
+        let error = Unsupported {
+            capability: Capability::Overrides,
+            version: Some(Version::new(0, 0, 3)),
+        };
+
+        let message = "overrides requires gateway 0.1.0 or newer, connected to 0.0.3";
+        assert_eq!(error.to_string(), message);
+    }
+
+    #[tokio::test]
+    async fn require() {
+        let url = "http://127.0.0.1:9/".parse().unwrap();
+        let glide = Client::builder()
+            .with_base_url(url)
+            .with_gateway_version(Version::new(0, 0, 3))
+            .build();
+
+        let data = ChatRequestOverride::new("Hi!").with_params(OpenAiParams::default());
+        let request = ChatRequest::new("Hello!").with_override("gpt", data);
+        let error = glide.lang.chat("myrouter", request).await.unwrap_err();
+        assert!(matches!(error, Error::Unsupported(x) if x.capability == Capability::Overrides));
+    }
+}
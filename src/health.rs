@@ -0,0 +1,200 @@
+#[cfg(feature = "runtime")]
+use std::future::Future;
+#[cfg(feature = "runtime")]
+use std::pin::Pin;
+#[cfg(feature = "runtime")]
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+
+#[cfg(feature = "runtime")]
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+#[cfg(feature = "runtime")]
+use tokio::time::{Interval, MissedTickBehavior};
+
+#[cfg(feature = "runtime")]
+use crate::{Client, Result};
+
+/// Latency above which the service is considered degraded by default.
+#[cfg(feature = "runtime")]
+const DEGRADED_LATENCY: Duration = Duration::from_secs(1);
+
+/// Health payload of the service and its client-measured latency.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub healthy: bool,
+    /// Duration of the whole health request, measured by the client.
+    #[serde(skip)]
+    pub latency: Duration,
+    /// Remaining fields of the payload.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// State of the service, as observed by the `HealthWatch`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    Healthy,
+    /// Healthy, but slower than the degradation threshold.
+    Degraded,
+    /// Reported itself unhealthy.
+    Unhealthy,
+    /// Failed to respond.
+    Unreachable,
+}
+
+/// Change of the [`HealthState`], yielded by the `HealthWatch`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthTransition {
+    /// Previous state, `None` for the first check.
+    pub previous: Option<HealthState>,
+    pub current: HealthState,
+    /// Report of the check that caused the transition,
+    /// or the error message of the failed check.
+    pub report: std::result::Result<HealthReport, String>,
+}
+
+#[cfg(feature = "runtime")]
+type Check = Pin<Box<dyn Future<Output = Result<HealthReport>> + Send>>;
+
+/// Stream of [`HealthTransition`]s, created with [`Client::watch_health`].
+///
+/// Checks the service health every interval and yields only when its
+/// [`HealthState`] changes, starting with the first check.
+#[cfg(feature = "runtime")]
+#[must_use = "streams do nothing unless you poll them"]
+pub struct HealthWatch {
+    client: Client,
+    interval: Interval,
+    degraded: Duration,
+    state: Option<HealthState>,
+    check: Option<Check>,
+}
+
+#[cfg(feature = "runtime")]
+impl HealthWatch {
+    /// Creates a new [`HealthWatch`].
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the `interval` is zero or called outside of the `tokio` runtime.
+    pub(crate) fn new(client: Client, interval: Duration) -> Self {
+        let mut interval = tokio::time::interval(interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        Self {
+            client,
+            interval,
+            degraded: DEGRADED_LATENCY,
+            state: None,
+            check: None,
+        }
+    }
+
+    /// Overrides the latency above which the service is considered degraded.
+    ///
+    /// Default value: `1s`
+    pub fn with_degraded_latency(mut self, latency: Duration) -> Self {
+        self.degraded = latency;
+        self
+    }
+
+    /// Returns the latest observed [`HealthState`], if any.
+    #[inline]
+    #[must_use]
+    pub fn state(&self) -> Option<HealthState> {
+        self.state
+    }
+
+    fn classify(&self, report: &Result<HealthReport>) -> HealthState {
+        match report {
+            Err(_) => HealthState::Unreachable,
+            Ok(x) if !x.healthy => HealthState::Unhealthy,
+            Ok(x) if x.latency > self.degraded => HealthState::Degraded,
+            Ok(_) => HealthState::Healthy,
+        }
+    }
+}
+
+#[cfg(feature = "runtime")]
+impl Stream for HealthWatch {
+    type Item = HealthTransition;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.check.is_none() {
+                ready!(self.interval.poll_tick(cx));
+                let client = self.client.clone();
+                self.check = Some(Box::pin(async move { client.health_report().await }));
+            }
+
+            let check = self.check.as_mut().expect("should be scheduled");
+            let report = ready!(check.as_mut().poll(cx));
+            self.check = None;
+
+            let current = self.classify(&report);
+            let previous = self.state.replace(current);
+            if previous != Some(current) {
+                return Poll::Ready(Some(HealthTransition {
+                    previous,
+                    current,
+                    report: report.map_err(|x| x.to_string()),
+                }));
+            }
+        }
+    }
+}
+
+#[cfg(feature = "runtime")]
+impl std::fmt::Debug for HealthWatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HealthWatch")
+            .field("period", &self.interval.period())
+            .field("degraded", &self.degraded)
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, feature = "runtime"))]
+mod test {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::health::{HealthReport, HealthState, HealthTransition};
+    use crate::Client;
+
+    #[tokio::test]
+    async fn classify() {
+        let watch = Client::default()
+            .watch_health(Duration::from_secs(1))
+            .with_degraded_latency(Duration::from_millis(100));
+
+        let mut report: HealthReport =
+            serde_json::from_value(json!({ "healthy": true, "version": "0.1.0" })).unwrap();
+        assert_eq!(report.extra["version"], "0.1.0");
+        assert_eq!(watch.classify(&Ok(report.clone())), HealthState::Healthy);
+
+        report.latency = Duration::from_millis(200);
+        assert_eq!(watch.classify(&Ok(report.clone())), HealthState::Degraded);
+
+        report.healthy = false;
+        assert_eq!(watch.classify(&Ok(report)), HealthState::Unhealthy);
+    }
+
+    #[test]
+    fn transition() {
+        let transition = HealthTransition {
+            previous: Some(HealthState::Healthy),
+            current: HealthState::Unreachable,
+            report: Err("connection refused".to_owned()),
+        };
+
+        let value = serde_json::to_value(&transition).unwrap();
+        let value: HealthTransition = serde_json::from_value(value).unwrap();
+        assert_eq!(value, transition.clone());
+    }
+}
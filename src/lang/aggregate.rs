@@ -0,0 +1,316 @@
+//! Aggregation of a streamed reply into a [`ChatResponse`].
+//!
+//! #### Example
+//!
+//! ```rust,no_run
+//! use futures::{SinkExt, StreamExt};
+//! use glide_rs::lang::aggregate::{AggregateEvent, ChatStreamExt};
+//! use glide_rs::lang::chat_stream::ChatStreamRequest;
+//! use glide_rs::Client;
+//!
+//! # #[cfg(feature = "streaming")]
+//! # let _ = async {
+//! let glide = Client::default();
+//! let mut chat = glide.lang.stream("myrouter").await?;
+//! chat.send(ChatStreamRequest::new("Hello!")).await?;
+//!
+//! let mut reply = chat.aggregate();
+//! while let Some(event) = reply.next().await {
+//!     match event? {
+//!         AggregateEvent::Delta(x) => print!("{}", x.content()),
+//!         AggregateEvent::Response(x) => println!("\n{:?}", x.model_response.token_count),
+//!     }
+//! }
+//! # Ok::<_, glide_rs::Error>(())
+//! # };
+//! ```
+
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+
+use crate::lang::chat::{
+    ChatMessage, ChatResponse, FinishReason, Metadata, ModelResponse, Role, Timestamp, TokenUsage,
+};
+use crate::lang::chat_stream::{ChatStreamChunk, ChatStreamEvent, ChatStreamMessage};
+use crate::lang::delta::Deltas;
+use crate::types::ErrorResponse;
+use crate::{Error, Result};
+
+/// Item of the [`Aggregate`] stream.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AggregateEvent {
+    /// Content delta, as received.
+    Delta(ChatStreamChunk),
+    /// Complete reply, always the last item.
+    Response(ChatResponse),
+}
+
+/// Extension of streams of [`ChatStreamMessage`]s, e.g. [`Chat`] or [`MultiplexedStream`].
+///
+/// [`Chat`]: crate::lang::Chat
+/// [`MultiplexedStream`]: crate::lang::MultiplexedStream
+pub trait ChatStreamExt: Stream<Item = Result<ChatStreamMessage>> + Sized {
+    /// Returns the [`Aggregate`] of the reply to the first received request id.
+    #[inline]
+    fn aggregate(self) -> Aggregate<Self> {
+        Aggregate::new(self)
+    }
+
+    /// Returns the [`Deltas`] of the reply to the first received request id.
+    #[inline]
+    fn deltas(self) -> Deltas<Self> {
+        Deltas::new(self)
+    }
+}
+
+impl<T> ChatStreamExt for T where T: Stream<Item = Result<ChatStreamMessage>> {}
+
+/// Stream of [`AggregateEvent`]s, created with [`ChatStreamExt::aggregate`].
+///
+/// Yields deltas while accumulating the reply, then the [`ChatResponse`]
+/// once the end-of-stream message arrives. Messages of other requests are skipped.
+#[must_use = "streams do nothing unless you poll them"]
+#[derive(Debug)]
+pub struct Aggregate<S> {
+    inner: S,
+    id: Option<String>,
+    reply: Option<Reply>,
+    response: Option<ChatResponse>,
+    done: bool,
+}
+
+impl<S> Aggregate<S> {
+    /// Creates a new [`Aggregate`] of the reply to the first received request id.
+    #[inline]
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            id: None,
+            reply: None,
+            response: None,
+            done: false,
+        }
+    }
+
+    /// Aggregates the reply to the specified request id.
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.id = Some(id.to_owned());
+        self
+    }
+
+    /// Returns the content accumulated so far.
+    #[inline]
+    #[must_use]
+    pub fn content(&self) -> &str {
+        self.reply.as_ref().map_or("", |x| x.content.as_str())
+    }
+
+    /// Returns the underlying stream.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S> Aggregate<S>
+where
+    S: Stream<Item = Result<ChatStreamMessage>> + Unpin,
+{
+    /// Drains the stream, discarding deltas, and returns the [`ChatResponse`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the stream fails, reports an error
+    /// or ends before the end-of-stream message.
+    ///
+    /// [`Error`]: crate::Error
+    pub async fn response(mut self) -> Result<ChatResponse> {
+        while let Some(event) = self.next().await {
+            if let AggregateEvent::Response(x) = event? {
+                return Ok(x);
+            }
+        }
+
+        Err(incomplete())
+    }
+}
+
+impl<S> Stream for Aggregate<S>
+where
+    S: Stream<Item = Result<ChatStreamMessage>> + Unpin,
+{
+    type Item = Result<AggregateEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        if let Some(response) = this.response.take() {
+            this.done = true;
+            return Poll::Ready(Some(Ok(AggregateEvent::Response(response))));
+        }
+
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+
+            let message = match ready!(this.inner.poll_next_unpin(cx)) {
+                Some(Ok(x)) => x,
+                Some(Err(x)) => return Poll::Ready(Some(Err(x))),
+                None => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(incomplete())));
+                }
+            };
+
+            if this.id.get_or_insert_with(|| message.id.clone()) != &message.id {
+                continue;
+            }
+
+            let reply = this.reply.get_or_insert_with(|| Reply::new(&message));
+            let next = match message.event {
+                ChatStreamEvent::Chunk(x) => {
+                    reply.push(&x);
+                    AggregateEvent::Delta(x)
+                }
+                ChatStreamEvent::End(x) => {
+                    reply.push(&x.chunk);
+                    let response = reply.build(x.finish_reason, x.token_usage);
+                    if x.chunk.content().is_empty() {
+                        this.done = true;
+                        AggregateEvent::Response(response)
+                    } else {
+                        this.response = Some(response);
+                        AggregateEvent::Delta(x.chunk)
+                    }
+                }
+                ChatStreamEvent::Error(x) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(ErrorResponse::from(x).into())));
+                }
+            };
+
+            return Poll::Ready(Some(Ok(next)));
+        }
+    }
+}
+
+/// Reply accumulated so far.
+#[derive(Debug)]
+struct Reply {
+    id: String,
+    created_at: Timestamp,
+    router_id: String,
+    chunk: Option<ChatStreamChunk>,
+    content: String,
+    metadata: Metadata,
+}
+
+impl Reply {
+    fn new(message: &ChatStreamMessage) -> Self {
+        Self {
+            id: message.id.clone(),
+            created_at: message.created_at,
+            router_id: message.router_id.clone(),
+            chunk: None,
+            content: String::new(),
+            metadata: message.metadata.clone().unwrap_or_default(),
+        }
+    }
+
+    fn push(&mut self, chunk: &ChatStreamChunk) {
+        self.content.push_str(&chunk.content());
+        if let Some(metadata) = &chunk.metadata {
+            let entries = metadata.0.iter().map(|(k, v)| (k.clone(), v.clone()));
+            self.metadata.0.extend(entries);
+        }
+
+        self.chunk = Some(chunk.clone());
+    }
+
+    fn build(&mut self, finish_reason: FinishReason, usage: Option<TokenUsage>) -> ChatResponse {
+        let chunk = self.chunk.take().expect("should have a chunk");
+        let mut metadata = std::mem::take(&mut self.metadata);
+        let finish_reason = Value::String(finish_reason.into());
+        metadata.0.insert("finish_reason".to_owned(), finish_reason);
+
+        let message = ChatMessage::new(&self.content).with_role(Role::Assistant);
+        ChatResponse {
+            cached: chunk.cached,
+            created_at: self.created_at,
+            id: self.id.clone(),
+            model_id: chunk.model_id,
+            model_name: chunk.model_name,
+            model_response: ModelResponse {
+                message,
+                metadata: Some(metadata),
+                token_count: usage.unwrap_or_default(),
+            },
+            provider_id: chunk.provider_id,
+            router_id: self.router_id.clone(),
+        }
+    }
+}
+
+fn incomplete() -> Error {
+    let error = "stream ended before the end-of-stream message";
+    io::Error::new(io::ErrorKind::UnexpectedEof, error).into()
+}
+
+#[cfg(test)]
+mod test {
+    use futures::{stream, StreamExt};
+    use serde_json::json;
+
+    use crate::fixtures::message;
+    use crate::lang::aggregate::{AggregateEvent, ChatStreamExt};
+    use crate::lang::chat::FinishReason;
+    use crate::Error;
+
+    #[tokio::test]
+    async fn aggregate() {
+        let messages = vec![
+            Ok(message("a", "Hel", false)),
+            Ok(message("b", "Bon", false)),
+            Ok(message("a", "lo", false)),
+            Ok(message("a", "!", true)),
+        ];
+
+        let mut reply = stream::iter(messages).aggregate();
+        let mut deltas = Vec::new();
+        let mut response = None;
+        while let Some(event) = reply.next().await {
+            match event.unwrap() {
+                AggregateEvent::Delta(x) => deltas.push(x.content().into_owned()),
+                AggregateEvent::Response(x) => response = Some(x),
+            }
+        }
+
+        assert_eq!(deltas, ["Hel", "lo", "!"]);
+        let response = response.unwrap();
+        assert_eq!(response.id, "a");
+        assert_eq!(response.content(), "Hello!");
+        assert_eq!(response.model_id, "gpt");
+        assert_eq!(response.model_response.token_count.total_tokens, 5);
+        let finish_reason = response.model_response.finish_reason();
+        assert_eq!(finish_reason, Some(FinishReason::Complete));
+
+        let messages = vec![Ok(message("a", "Hel", false))];
+        let result = stream::iter(messages).aggregate().response().await;
+        assert!(matches!(result, Err(Error::Io(_))));
+
+        let error = json!({
+            "id": "a",
+            "created_at": 1700000000,
+            "router_id": "myrouter",
+            "error": { "name": "all_models_unavailable", "message": "no models" },
+        });
+
+        let messages = vec![Ok(serde_json::from_value(error).unwrap())];
+        let result = stream::iter(messages).aggregate().response().await;
+        assert!(matches!(result, Err(Error::Api(_))));
+    }
+}
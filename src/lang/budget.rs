@@ -0,0 +1,428 @@
+//! Spending limits enforced before chat requests are sent.
+//!
+//! Every chat request reserves its estimated [`Cost`] in all matching
+//! [`Budget`]s and is rejected with [`Error::BudgetExceeded`] if any limit
+//! would be exceeded. The reservation is reconciled with the real
+//! [`TokenUsage`] of the response, or released if the request fails.
+//!
+//! [`Error::BudgetExceeded`]: crate::Error::BudgetExceeded
+//! [`TokenUsage`]: crate::lang::chat::TokenUsage
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
+
+use crate::lang::chat::{ChatRequest, ChatResponse, TokenUsage};
+use crate::lang::cost::{Cost, Price, Pricing};
+use crate::lang::tokenizer::Tokenizer;
+use crate::Result;
+
+/// Response tokens reserved for every request by default.
+const RESPONSE_TOKENS: usize = 1024;
+
+/// Chat requests a [`Budget`] applies to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BudgetScope {
+    /// All requests.
+    All,
+    /// Requests to the `router`.
+    Router(String),
+    /// Requests with the custom tag, see [`Router::with_tag`].
+    ///
+    /// [`Router::with_tag`]: crate::lang::Router::with_tag
+    Tag(String),
+}
+
+impl BudgetScope {
+    /// Creates a new [`BudgetScope::Router`].
+    #[inline]
+    #[must_use]
+    pub fn router(router: &str) -> Self {
+        Self::Router(router.to_owned())
+    }
+
+    /// Creates a new [`BudgetScope::Tag`].
+    #[inline]
+    #[must_use]
+    pub fn tag(tag: &str) -> Self {
+        Self::Tag(tag.to_owned())
+    }
+
+    fn matches(&self, router: &str, tag: Option<&str>) -> bool {
+        match self {
+            Self::All => true,
+            Self::Router(x) => x == router,
+            Self::Tag(x) => Some(x.as_str()) == tag,
+        }
+    }
+}
+
+impl fmt::Display for BudgetScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::All => f.write_str("all"),
+            Self::Router(x) => write!(f, "router:{x}"),
+            Self::Tag(x) => write!(f, "tag:{x}"),
+        }
+    }
+}
+
+/// Spending limit in `USD` of a [`BudgetScope`].
+#[must_use]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Budget {
+    pub scope: BudgetScope,
+    pub limit: f64,
+    /// Length of the fixed window, aligned to the `UNIX` epoch.
+    ///
+    /// The limit never resets if `None`.
+    pub window: Option<Duration>,
+}
+
+impl Budget {
+    /// Creates a new [`Budget`] that never resets.
+    #[inline]
+    pub const fn new(scope: BudgetScope, limit: f64) -> Self {
+        Self {
+            scope,
+            limit,
+            window: None,
+        }
+    }
+
+    /// Creates a new [`Budget`] that resets at midnight `UTC`.
+    #[inline]
+    pub const fn daily(scope: BudgetScope, limit: f64) -> Self {
+        Self::new(scope, limit).with_window(Duration::from_secs(24 * 60 * 60))
+    }
+
+    /// Resets the limit every `window`.
+    #[inline]
+    pub const fn with_window(mut self, window: Duration) -> Self {
+        self.window = Some(window);
+        self
+    }
+
+    /// Returns the key of the counter of the current window.
+    fn key(&self, now: SystemTime) -> String {
+        let window = self.window.map(|x| x.as_secs().max(1));
+        match window {
+            Some(window) => {
+                let secs = now
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default();
+                let start = secs.as_secs() / window * window;
+                format!("{}/{window}s/{start}", self.scope)
+            }
+            None => self.scope.to_string(),
+        }
+    }
+}
+
+/// Storage of [`Budget`] counters, e.g. shared between processes.
+///
+/// Counters are identified by keys unique to the [`Budget`] and its window.
+pub trait BudgetStore: fmt::Debug + Send + Sync {
+    /// Atomically adds the `amount` to the counter unless it would exceed the `limit`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the current counter if the `limit` would be exceeded.
+    fn reserve(&self, key: &str, amount: f64, limit: f64) -> Result<f64, f64>;
+
+    /// Adds the `amount`, possibly negative, to the counter.
+    fn adjust(&self, key: &str, amount: f64);
+
+    /// Returns the counter, or zero if it does not exist.
+    fn get(&self, key: &str) -> f64;
+}
+
+/// In-memory [`BudgetStore`], shared by all clones.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore(Arc<Mutex<HashMap<String, f64>>>);
+
+impl MemoryStore {
+    /// Creates a new empty [`MemoryStore`].
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, f64>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl BudgetStore for MemoryStore {
+    fn reserve(&self, key: &str, amount: f64, limit: f64) -> Result<f64, f64> {
+        let mut counters = self.lock();
+        let counter = counters.entry(key.to_owned()).or_default();
+        if *counter + amount > limit {
+            return Err(*counter);
+        }
+
+        *counter += amount;
+        Ok(*counter)
+    }
+
+    fn adjust(&self, key: &str, amount: f64) {
+        *self.lock().entry(key.to_owned()).or_default() += amount;
+    }
+
+    fn get(&self, key: &str) -> f64 {
+        self.lock().get(key).copied().unwrap_or_default()
+    }
+}
+
+/// Request rejected because it would exceed the [`Budget`].
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{scope} would exceed the limit of {limit} USD: spent {spent}, estimated {estimated}")]
+pub struct BudgetExceeded {
+    pub scope: BudgetScope,
+    pub limit: f64,
+    pub spent: f64,
+    pub estimated: f64,
+}
+
+/// Set of [`Budget`]s sharing the [`BudgetStore`].
+///
+/// The estimated [`Cost`] of a request is the [`Tokenizer`] estimate of
+/// its prompt plus the reserved response tokens, priced at the most
+/// expensive model of the [`Pricing`] unless overridden.
+#[must_use]
+#[derive(Debug, Clone)]
+pub struct Budgets {
+    budgets: Vec<Budget>,
+    store: Arc<dyn BudgetStore>,
+    pricing: Arc<Pricing>,
+    tokenizer: Tokenizer,
+    estimate: Option<Price>,
+    response_tokens: usize,
+}
+
+impl Budgets {
+    /// Creates a new [`Budgets`] with the [`BudgetStore`] and the [`Pricing`].
+    pub fn new(store: impl BudgetStore + 'static, pricing: Pricing) -> Self {
+        Self {
+            budgets: Vec::new(),
+            store: Arc::new(store),
+            pricing: Arc::new(pricing),
+            tokenizer: Tokenizer::default(),
+            estimate: None,
+            response_tokens: RESPONSE_TOKENS,
+        }
+    }
+
+    /// Adds the [`Budget`].
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budgets.push(budget);
+        self
+    }
+
+    /// Overrides the [`Price`] used to estimate the cost of requests.
+    pub fn with_estimate(mut self, price: Price) -> Self {
+        self.estimate = Some(price);
+        self
+    }
+
+    /// Overrides the number of response tokens reserved for every request.
+    ///
+    /// Default value: `1024`
+    pub fn with_response_tokens(mut self, tokens: usize) -> Self {
+        self.response_tokens = tokens;
+        self
+    }
+
+    /// Overrides the [`Tokenizer`] used to estimate prompt tokens.
+    pub fn with_tokenizer(mut self, tokenizer: Tokenizer) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// Returns all [`Budget`]s.
+    #[inline]
+    pub fn budgets(&self) -> &[Budget] {
+        &self.budgets
+    }
+
+    /// Returns the amount spent in the current window of the [`Budget`].
+    #[must_use]
+    pub fn spent(&self, budget: &Budget) -> f64 {
+        self.store.get(&budget.key(SystemTime::now()))
+    }
+
+    /// Returns the estimated [`Cost`] of the [`ChatRequest`].
+    #[must_use]
+    pub fn estimate(&self, request: &ChatRequest) -> Cost {
+        let tokens = |x: usize| i32::try_from(x).unwrap_or(i32::MAX);
+        let usage = TokenUsage {
+            prompt_tokens: tokens(self.tokenizer.count_request(request)),
+            response_tokens: tokens(self.response_tokens),
+            total_tokens: 0,
+        };
+
+        let price = self.estimate.unwrap_or_else(|| {
+            let prices = self.pricing.0.values().flat_map(HashMap::values);
+            prices.fold(Price::default(), |x, y| {
+                Price::new(x.input.max(y.input), x.output.max(y.output))
+            })
+        });
+
+        price.cost(&usage)
+    }
+
+    /// Reserves the estimated [`Cost`] of the [`ChatRequest`] to the `router`
+    /// with the optional custom `tag` in all matching [`Budget`]s.
+    ///
+    /// Called by [`Language::chat`] when attached with [`Builder::with_budgets`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if any [`Budget`] would be exceeded.
+    ///
+    /// [`Language::chat`]: crate::lang::Language::chat
+    /// [`Builder::with_budgets`]: crate::Builder::with_budgets
+    /// [`Error`]: crate::Error
+    pub fn reserve(
+        &self,
+        router: &str,
+        request: &ChatRequest,
+        tag: Option<&str>,
+    ) -> Result<Reservation> {
+        let estimated = self.estimate(request).total();
+        let mut reservation = Reservation {
+            store: self.store.clone(),
+            pricing: self.pricing.clone(),
+            keys: Vec::new(),
+            estimated,
+        };
+
+        let now = SystemTime::now();
+        for budget in self.budgets.iter().filter(|x| x.scope.matches(router, tag)) {
+            let key = budget.key(now);
+            if let Err(spent) = self.store.reserve(&key, estimated, budget.limit) {
+                return Err(BudgetExceeded {
+                    scope: budget.scope.clone(),
+                    limit: budget.limit,
+                    spent,
+                    estimated,
+                }
+                .into());
+            }
+
+            reservation.keys.push(key);
+        }
+
+        Ok(reservation)
+    }
+}
+
+/// Estimated [`Cost`] reserved in [`Budget`]s.
+///
+/// Released when dropped without being settled.
+#[must_use]
+#[derive(Debug)]
+pub struct Reservation {
+    store: Arc<dyn BudgetStore>,
+    pricing: Arc<Pricing>,
+    keys: Vec<String>,
+    estimated: f64,
+}
+
+impl Reservation {
+    /// Returns the reserved amount.
+    #[inline]
+    #[must_use]
+    pub fn estimated(&self) -> f64 {
+        self.estimated
+    }
+
+    /// Replaces the reserved amount with the real [`Cost`] of the [`ChatResponse`].
+    ///
+    /// Releases the reserved amount if the response is cached,
+    /// keeps it if the model is not priced.
+    pub fn settle(mut self, response: &ChatResponse) {
+        let cost = self.pricing.cost(response).map(|x| x.total());
+        let delta = cost.map_or(0.0, |x| x - self.estimated);
+        for key in std::mem::take(&mut self.keys) {
+            self.store.adjust(&key, delta);
+        }
+    }
+
+    /// Keeps the reserved amount, e.g. when the request timed out
+    /// and its real [`Cost`] is unknown.
+    pub fn keep(mut self) {
+        self.keys.clear();
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        for key in &self.keys {
+            self.store.adjust(key, -self.estimated);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fixtures::response;
+    use crate::lang::budget::{Budget, BudgetScope, Budgets, MemoryStore};
+    use crate::lang::chat::ChatRequest;
+    use crate::lang::cost::{Price, Pricing};
+    use crate::Error;
+
+    fn budgets() -> Budgets {
+        let mut pricing = Pricing::new();
+        pricing.insert("openai", "gpt-4o", Price::new(1e6, 1e6));
+        Budgets::new(MemoryStore::new(), pricing)
+            .with_budget(Budget::daily(BudgetScope::All, 100.0))
+            .with_budget(Budget::new(BudgetScope::tag("search"), 30.0))
+            .with_response_tokens(10)
+    }
+
+    #[test]
+    fn reserve() {
+        let budgets = budgets();
+        let request = ChatRequest::new("Hello!");
+        assert_eq!(budgets.estimate(&request).total(), 19.0);
+
+        let all = budgets.budgets()[0].clone();
+        let search = budgets.budgets()[1].clone();
+
+        let reservation = budgets
+            .reserve("myrouter", &request, Some("search"))
+            .unwrap();
+        assert_eq!(budgets.spent(&all), 19.0);
+        reservation.settle(&response(5, 0));
+        assert_eq!(budgets.spent(&all), 5.0);
+        assert_eq!(budgets.spent(&search), 5.0);
+
+        let reservation = budgets
+            .reserve("myrouter", &request, Some("search"))
+            .unwrap();
+        let error = budgets
+            .reserve("myrouter", &request, Some("search"))
+            .unwrap_err();
+        assert!(matches!(error, Error::BudgetExceeded(x) if x.scope == search.scope));
+        assert_eq!(budgets.spent(&all), 24.0);
+
+        drop(reservation);
+        assert_eq!(budgets.spent(&search), 5.0);
+
+        let mut cached = response(5, 0);
+        cached.cached = true;
+        let reservation = budgets.reserve("myrouter", &request, None).unwrap();
+        reservation.settle(&cached);
+        assert_eq!(budgets.spent(&all), 5.0);
+
+        let reservation = budgets.reserve("myrouter", &request, None).unwrap();
+        reservation.keep();
+        assert_eq!(budgets.spent(&all), 24.0);
+        assert!(budgets
+            .reserve("myrouter", &ChatRequest::new("Hi!"), None)
+            .is_ok());
+    }
+}
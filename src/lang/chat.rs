@@ -1,20 +1,39 @@
 //! Request and response types for `/v1/language/{}/chat` endpoints.
 //!
 
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::lang::content::{Content, ContentPart};
+use crate::lang::params::ModelParams;
+use crate::lang::tools::{Tool, ToolCall};
 
 /// Unified chat request across all language models.
 #[must_use]
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ChatRequest {
     #[serde(rename = "message")]
     pub message: ChatMessage,
-    #[serde(rename = "message_history", skip_serializing_if = "Option::is_none")]
+    #[serde(
+        rename = "message_history",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub message_history: Option<Vec<ChatMessage>>,
-    #[serde(rename = "override_params", skip_serializing_if = "Option::is_none")]
+    #[serde(
+        rename = "override_params",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub override_params: Option<HashMap<String, ChatRequestOverride>>,
+    /// Tools the model may call.
+    #[serde(rename = "tools", default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
 }
 
 impl ChatRequest {
@@ -23,6 +42,19 @@ impl ChatRequest {
     pub fn new(message: impl Into<ChatMessage>) -> Self {
         Self::from(message.into())
     }
+
+    /// Attaches the [`ChatRequestOverride`] of the specified `model`.
+    pub fn with_override(mut self, model: &str, data: ChatRequestOverride) -> Self {
+        let overrides = self.override_params.get_or_insert_with(HashMap::new);
+        overrides.insert(model.to_owned(), data);
+        self
+    }
+
+    /// Attaches the [`Tool`] the model may call.
+    pub fn with_tool(mut self, tool: Tool) -> Self {
+        self.tools.get_or_insert_with(Vec::new).push(tool);
+        self
+    }
 }
 
 impl<T> From<T> for ChatRequest
@@ -34,16 +66,17 @@ where
             message: message.into(),
             message_history: None,
             override_params: None,
+            tools: None,
         }
     }
 }
 
 /// Unified chat response across all language models.
 #[must_use]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ChatResponse {
     pub cached: bool,
-    pub created_at: i32,
+    pub created_at: Timestamp,
     pub id: String,
     pub model_id: String,
     pub model_name: String,
@@ -53,23 +86,244 @@ pub struct ChatResponse {
 }
 
 impl ChatResponse {
-    /// Returns the reference to the model response.
+    /// Returns the text of the model response, with all text parts joined by a newline.
+    ///
+    /// Returns a [`Cow`] rather than `&str` since messages may consist of multiple parts.
     #[inline]
-    pub fn content(&self) -> &str {
-        &self.model_response.message.content
+    pub fn content(&self) -> Cow<'_, str> {
+        self.model_response.message.content.text()
     }
 }
 
 /// Unified response from the provider.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ModelResponse {
     pub message: ChatMessage,
-    pub metadata: Option<HashMap<String, String>>,
+    pub metadata: Option<Metadata>,
     pub token_count: TokenUsage,
 }
 
+impl ModelResponse {
+    /// Returns the tool calls requested by the model.
+    #[inline]
+    pub fn tool_calls(&self) -> &[ToolCall] {
+        self.message.tool_calls.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the reason the model stopped generating, if reported.
+    #[inline]
+    pub fn finish_reason(&self) -> Option<FinishReason> {
+        self.metadata.as_ref().and_then(Metadata::finish_reason)
+    }
+}
+
+/// Unix timestamp, in seconds.
+#[must_use]
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Timestamp(pub i64);
+
+impl Timestamp {
+    /// Returns the current [`Timestamp`].
+    #[inline]
+    pub fn now() -> Self {
+        SystemTime::now().into()
+    }
+
+    /// Returns the number of seconds since the Unix epoch.
+    #[inline]
+    #[must_use]
+    pub const fn as_secs(&self) -> i64 {
+        self.0
+    }
+
+    /// Converts the [`Timestamp`] into a [`SystemTime`].
+    ///
+    /// Returns `None` if the [`Timestamp`] is out of the range of [`SystemTime`].
+    #[inline]
+    #[must_use]
+    pub fn to_system_time(self) -> Option<SystemTime> {
+        let secs = Duration::from_secs(self.0.unsigned_abs());
+        match self.0 {
+            x if x < 0 => UNIX_EPOCH.checked_sub(secs),
+            _ => UNIX_EPOCH.checked_add(secs),
+        }
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(value: SystemTime) -> Self {
+        let secs = match value.duration_since(UNIX_EPOCH) {
+            Ok(x) => i64::try_from(x.as_secs()).unwrap_or(i64::MAX),
+            Err(x) => -i64::try_from(x.duration().as_secs()).unwrap_or(i64::MAX),
+        };
+
+        Self(secs)
+    }
+}
+
+/// Provider-specific response metadata.
+///
+/// Known keys are exposed with typed accessors,
+/// all keys remain available as raw `JSON` values.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Metadata(pub HashMap<String, Value>);
+
+impl Metadata {
+    /// Returns the raw value of the key.
+    #[inline]
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.0.get(key)
+    }
+
+    /// Returns the string value of the key.
+    #[inline]
+    #[must_use]
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(Value::as_str)
+    }
+
+    /// Returns the reference to all raw values.
+    #[inline]
+    #[must_use]
+    pub const fn raw(&self) -> &HashMap<String, Value> {
+        &self.0
+    }
+
+    /// Returns the reason the model stopped generating.
+    ///
+    /// Key: `finish_reason`.
+    #[inline]
+    pub fn finish_reason(&self) -> Option<FinishReason> {
+        self.get_str("finish_reason").map(FinishReason::from)
+    }
+
+    /// Returns the backend configuration fingerprint (`OpenAI`).
+    ///
+    /// Key: `system_fingerprint`.
+    #[inline]
+    #[must_use]
+    pub fn system_fingerprint(&self) -> Option<&str> {
+        self.get_str("system_fingerprint").filter(|x| !x.is_empty())
+    }
+
+    /// Returns the generation id (`Cohere`).
+    ///
+    /// Key: `generationId`.
+    #[inline]
+    #[must_use]
+    pub fn generation_id(&self) -> Option<&str> {
+        self.get_str("generationId")
+    }
+
+    /// Returns the provider response id (`Cohere`).
+    ///
+    /// Key: `responseId`.
+    #[inline]
+    #[must_use]
+    pub fn response_id(&self) -> Option<&str> {
+        self.get_str("responseId")
+    }
+
+    /// Returns the provider latency.
+    ///
+    /// Keys: `latency_ms` in milliseconds, or `latency` in milliseconds
+    /// or as a duration string, e.g. `1.5s`.
+    #[must_use]
+    pub fn latency(&self) -> Option<Duration> {
+        let millis = |x: &Value| {
+            let millis = match x {
+                Value::Number(x) => x.as_f64(),
+                Value::String(x) => x.parse::<f64>().ok(),
+                _ => None,
+            };
+
+            millis.and_then(|x| Duration::try_from_secs_f64(x / 1e3).ok())
+        };
+
+        if let Some(latency) = self.get("latency_ms") {
+            return millis(latency);
+        }
+
+        let latency = self.get("latency")?;
+        match latency {
+            Value::String(x) => crate::duration::parse(x).or_else(|| millis(latency)),
+            x => millis(x),
+        }
+    }
+}
+
+/// The reason the model stopped generating.
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum FinishReason {
+    /// The model completed the response.
+    Complete,
+    /// The response reached the maximum number of tokens.
+    MaxTokens,
+    /// The response was filtered by the provider.
+    ContentFiltered,
+    /// The model requested tool calls.
+    ToolCalls,
+    /// The reason is not a part of the implemented `API` spec.
+    Other(String),
+}
+
+impl FinishReason {
+    /// Returns the string representation used by the gateway.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Complete => "complete",
+            Self::MaxTokens => "max_tokens",
+            Self::ContentFiltered => "content_filtered",
+            Self::ToolCalls => "tool_calls",
+            Self::Other(x) => x.as_str(),
+        }
+    }
+}
+
+impl From<&str> for FinishReason {
+    fn from(value: &str) -> Self {
+        match value {
+            "complete" | "stop" | "end_turn" | "COMPLETE" => Self::Complete,
+            "max_tokens" | "length" | "MAX_TOKENS" => Self::MaxTokens,
+            "content_filtered" | "content_filter" => Self::ContentFiltered,
+            "tool_calls" | "tool_use" => Self::ToolCalls,
+            x => Self::Other(x.to_owned()),
+        }
+    }
+}
+
+impl From<String> for FinishReason {
+    #[inline]
+    fn from(value: String) -> Self {
+        Self::from(value.as_str())
+    }
+}
+
+impl From<FinishReason> for String {
+    #[inline]
+    fn from(value: FinishReason) -> Self {
+        value.as_str().to_owned()
+    }
+}
+
+impl fmt::Display for FinishReason {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Prompt, response and total token usage.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TokenUsage {
     pub prompt_tokens: i32,
     pub response_tokens: i32,
@@ -78,10 +332,11 @@ pub struct TokenUsage {
 
 /// Content and role of the message.
 #[must_use]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ChatMessage {
     /// The content of the message.
-    pub content: String,
+    #[serde(default)]
+    pub content: Content,
     /// The name of the author of this message.
     ///
     /// May contain a-z, A-Z, 0-9, and underscores,
@@ -90,17 +345,36 @@ pub struct ChatMessage {
     pub name: Option<String>,
     /// The role of the author of this message.
     ///
-    /// One of system, user, or assistant.
+    /// One of system, user, assistant, or tool.
+    #[serde(default)]
     pub role: Option<Role>,
+    /// The tool calls requested by the model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    /// The tool call this [`Role::Tool`] message is responding to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
 }
 
 impl ChatMessage {
     /// Creates a new [`ChatMessage`].
     pub fn new(content: &str) -> Self {
+        Self::from_content(content.into())
+    }
+
+    /// Creates a new [`ChatMessage`] with multimodal content.
+    pub fn from_parts(parts: Vec<ContentPart>) -> Self {
+        Self::from_content(parts.into())
+    }
+
+    /// Creates a new [`ChatMessage`] with the provided [`Content`].
+    pub const fn from_content(content: Content) -> Self {
         Self {
-            content: content.to_owned(),
+            content,
             name: None,
             role: None,
+            tool_calls: None,
+            tool_call_id: None,
         }
     }
 
@@ -109,11 +383,23 @@ impl ChatMessage {
         self.role = Some(Role::System);
         self
     }
+
+    /// Overrides the default [`Role::User`] with the provided [`Role`].
+    pub const fn with_role(mut self, role: Role) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    /// Returns the [`Role`] of the author, defaulting to [`Role::User`].
+    #[inline]
+    pub fn role(&self) -> Role {
+        self.role.unwrap_or_default()
+    }
 }
 
 impl From<String> for ChatMessage {
     fn from(value: String) -> Self {
-        Self::new(&value)
+        Self::from_content(value.into())
     }
 }
 
@@ -125,9 +411,9 @@ impl From<&str> for ChatMessage {
 
 /// The role of the author of this message.
 ///
-/// One of system, user, or assistant.
+/// One of system, user, assistant, or tool.
 #[must_use]
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Role {
     #[serde(rename = "system")]
     System,
@@ -136,11 +422,184 @@ pub enum Role {
     User,
     #[serde(rename = "assistant")]
     Assistant,
+    #[serde(rename = "tool")]
+    Tool,
 }
 
 /// Override of a single chat request.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ChatRequestOverride {
     #[serde(rename = "message")]
     pub message: ChatMessage,
+    /// Provider-specific model parameters.
+    #[serde(
+        flatten,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_params"
+    )]
+    pub params: Option<ModelParams>,
+}
+
+impl ChatRequestOverride {
+    /// Creates a new [`ChatRequestOverride`].
+    pub fn new(message: impl Into<ChatMessage>) -> Self {
+        Self {
+            message: message.into(),
+            params: None,
+        }
+    }
+
+    /// Attaches the provider-specific [`ModelParams`].
+    pub fn with_params(mut self, params: impl Into<ModelParams>) -> Self {
+        self.params = Some(params.into());
+        self
+    }
+}
+
+/// Deserializes the flattened [`ModelParams`], `None` if there are no parameters.
+fn deserialize_params<'de, D>(deserializer: D) -> Result<Option<ModelParams>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::Error as _;
+
+    let params = serde_json::Map::deserialize(deserializer)?;
+    if params.is_empty() {
+        return Ok(None);
+    }
+
+    let params = ModelParams::deserialize(Value::Object(params));
+    params.map(Some).map_err(D::Error::custom)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use serde_json::json;
+
+    use crate::lang::chat::{
+        ChatRequest, ChatRequestOverride, ChatResponse, FinishReason, Metadata, Timestamp,
+    };
+    use crate::lang::content::{Content, ContentPart};
+    use crate::lang::params::{BedrockParams, ModelParams, OpenAiParams};
+
+    #[test]
+    fn override_params() {
+        let params = OpenAiParams {
+            temperature: Some(0.5),
+            stop: Some(vec!["END".to_owned()]),
+            ..OpenAiParams::default()
+        };
+
+        let data = ChatRequestOverride::new("Hi!").with_params(params);
+        let request = ChatRequest::new("Hello!").with_override("gpt", data);
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(
+            value["override_params"]["gpt"],
+            json!({
+                "message": { "content": "Hi!", "role": null },
+                "openai": { "temperature": 0.5, "stop": ["END"] },
+            })
+        );
+    }
+
+    #[test]
+    fn provider_params() {
+        let params = BedrockParams {
+            max_tokens: Some(128),
+            ..BedrockParams::default()
+        };
+
+        let data = ChatRequestOverride::new("Hi!").with_params(params);
+        let value = serde_json::to_value(&data).unwrap();
+        assert_eq!(value["bedrock"], json!({ "maxTokenCount": 128 }));
+
+        let params = json!({ "seed": 42 }).as_object().cloned().unwrap();
+        let data = ChatRequestOverride::new("Hi!").with_params(ModelParams::Raw(params));
+        let value = serde_json::to_value(&data).unwrap();
+        assert_eq!(value["seed"], json!(42));
+    }
+
+    #[test]
+    fn timestamp() {
+        let timestamp: Timestamp = serde_json::from_value(json!(4_102_444_800_i64)).unwrap();
+        let time = timestamp.to_system_time().unwrap();
+        assert_eq!(time, UNIX_EPOCH + Duration::from_secs(4_102_444_800));
+        assert_eq!(Timestamp::from(time), timestamp);
+        // Out of range on some platforms, must not panic.
+        let _ = Timestamp(i64::MIN).to_system_time();
+        let _ = Timestamp(i64::MAX).to_system_time();
+    }
+
+    #[test]
+    fn metadata() {
+        let metadata: Metadata = serde_json::from_value(json!({
+            "finish_reason": "length",
+            "system_fingerprint": "fp_44709d6fcb",
+            "latency": "1.5s",
+            "region": "eu",
+        }))
+        .unwrap();
+
+        assert_eq!(metadata.finish_reason(), Some(FinishReason::MaxTokens));
+        assert_eq!(metadata.system_fingerprint(), Some("fp_44709d6fcb"));
+        assert_eq!(metadata.latency(), Some(Duration::from_millis(1500)));
+        assert_eq!(metadata.get_str("region"), Some("eu"));
+    }
+
+    #[test]
+    fn request_wire_format() {
+        let value = json!({
+            "message": { "content": "Hello!", "role": "user" },
+            "message_history": [
+                { "content": "Be concise.", "role": "system", "name": "rules" },
+            ],
+            "override_params": {
+                "gpt": {
+                    "message": { "content": "Hi!", "role": null },
+                    "openai": { "temperature": 0.5 },
+                },
+                "cohere": { "message": { "content": "Hi!", "role": null } },
+            },
+        });
+
+        let request: ChatRequest = serde_json::from_value(value.clone()).unwrap();
+        assert!(request.override_params.as_ref().unwrap()["cohere"]
+            .params
+            .is_none());
+        assert_eq!(serde_json::to_value(&request).unwrap(), value);
+        assert_eq!(request.clone(), request);
+    }
+
+    #[test]
+    fn response_wire_format() {
+        let value = json!({
+            "id": "2b5f8f1e",
+            "created_at": 1_714_000_000,
+            "provider_id": "openai",
+            "router_id": "myrouter",
+            "model_id": "openai",
+            "model_name": "gpt-3.5-turbo",
+            "cached": false,
+            "model_response": {
+                "metadata": { "system_fingerprint": "fp_44709d6fcb" },
+                "message": { "content": "Hi!", "role": "assistant" },
+                "token_count": { "prompt_tokens": 9, "response_tokens": 2, "total_tokens": 11 },
+            },
+        });
+
+        let response: ChatResponse = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(response.content(), "Hi!");
+        assert_eq!(serde_json::to_value(&response).unwrap(), value);
+        assert_eq!(response.clone(), response);
+
+        let mut response = response;
+        response.model_response.message.content = Content::Parts(vec![
+            ContentPart::text("Hi!"),
+            ContentPart::image_url("https://example.com/image.png"),
+            ContentPart::text("Bye!"),
+        ]);
+        assert_eq!(response.content(), "Hi!\nBye!");
+    }
 }
@@ -0,0 +1,406 @@
+//! Request and response types for `/v1/language/{}/chatStream` endpoints.
+//!
+//! Every [`ChatStreamRequest`] carries a client-generated id, repeated by all
+//! [`ChatStreamMessage`]s of its response.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::lang::chat::{
+    ChatMessage, ChatRequest, ChatRequestOverride, FinishReason, Metadata, Timestamp, TokenUsage,
+};
+use crate::types::ErrorResponse;
+
+/// Streaming chat request, a single turn of the conversation.
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatStreamRequest {
+    /// Client-generated id, unique within the connection.
+    pub id: String,
+    pub message: ChatMessage,
+    #[serde(default)]
+    pub message_history: Vec<ChatMessage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub override_params: Option<HashMap<String, ChatRequestOverride>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Metadata>,
+}
+
+impl ChatStreamRequest {
+    /// Creates a new [`ChatStreamRequest`] with a generated id.
+    #[inline]
+    pub fn new(message: impl Into<ChatMessage>) -> Self {
+        Self::from(message.into())
+    }
+
+    /// Overrides the generated id.
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.id = id.to_owned();
+        self
+    }
+
+    /// Attaches the message history, sent before the message.
+    pub fn with_history(mut self, history: Vec<ChatMessage>) -> Self {
+        self.message_history = history;
+        self
+    }
+
+    /// Attaches the [`ChatRequestOverride`] of the specified `model`.
+    pub fn with_override(mut self, model: &str, data: ChatRequestOverride) -> Self {
+        let overrides = self.override_params.get_or_insert_with(HashMap::new);
+        overrides.insert(model.to_owned(), data);
+        self
+    }
+}
+
+impl<T> From<T> for ChatStreamRequest
+where
+    T: Into<ChatMessage>,
+{
+    #[inline]
+    fn from(message: T) -> Self {
+        Self::from(ChatRequest::new(message))
+    }
+}
+
+/// Drops the `tools` of the [`ChatRequest`], as the streaming endpoint does not
+/// support tool calls. Use [`Language::chat`] for requests with tools.
+///
+/// [`Language::chat`]: crate::lang::Language::chat
+impl From<ChatRequest> for ChatStreamRequest {
+    fn from(data: ChatRequest) -> Self {
+        Self {
+            id: next_id(),
+            message: data.message,
+            message_history: data.message_history.unwrap_or_default(),
+            override_params: data.override_params,
+            metadata: None,
+        }
+    }
+}
+
+/// Returns the next request id, unique within the process.
+pub(crate) fn next_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let counter = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let since = SystemTime::now().duration_since(UNIX_EPOCH);
+    let nanos = since.map(|x| x.as_nanos()).unwrap_or_default();
+    format!("{nanos:x}-{:x}-{counter:x}", std::process::id())
+}
+
+/// Single message of the streaming chat response.
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "wire::Message", into = "wire::Message")]
+pub struct ChatStreamMessage {
+    /// Id of the [`ChatStreamRequest`].
+    pub id: String,
+    pub created_at: Timestamp,
+    pub router_id: String,
+    pub metadata: Option<Metadata>,
+    pub event: ChatStreamEvent,
+}
+
+impl ChatStreamMessage {
+    /// Returns the content delta of the message, empty for errors.
+    #[inline]
+    pub fn content(&self) -> Cow<'_, str> {
+        match &self.event {
+            ChatStreamEvent::Chunk(x) => x.content(),
+            ChatStreamEvent::End(x) => x.chunk.content(),
+            ChatStreamEvent::Error(_) => Cow::Borrowed(""),
+        }
+    }
+
+    /// Returns `true` if no more messages follow for the request.
+    #[inline]
+    #[must_use]
+    pub fn is_final(&self) -> bool {
+        !matches!(self.event, ChatStreamEvent::Chunk(_))
+    }
+}
+
+/// Payload of the [`ChatStreamMessage`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatStreamEvent {
+    /// Content delta of the response.
+    Chunk(ChatStreamChunk),
+    /// Last delta of the response.
+    End(ChatStreamEnd),
+    /// Failure of the request, no more messages follow.
+    Error(ChatStreamError),
+}
+
+/// Content delta from the provider.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatStreamChunk {
+    pub model_id: String,
+    pub model_name: String,
+    pub provider_id: String,
+    #[serde(default)]
+    pub cached: bool,
+    /// Delta of the message content.
+    pub message: ChatMessage,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Metadata>,
+}
+
+impl ChatStreamChunk {
+    /// Returns the content delta, with all text parts joined by a newline.
+    #[inline]
+    pub fn content(&self) -> Cow<'_, str> {
+        self.message.content.text()
+    }
+}
+
+/// Last delta of the response, with the reason the model stopped generating.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatStreamEnd {
+    pub chunk: ChatStreamChunk,
+    pub finish_reason: FinishReason,
+    /// Token usage of the whole response, if reported.
+    pub token_usage: Option<TokenUsage>,
+}
+
+/// Failure of the streaming chat request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+#[error("{name}: {message}")]
+pub struct ChatStreamError {
+    #[serde(alias = "error_code")]
+    pub name: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<FinishReason>,
+}
+
+impl From<ChatStreamError> for ErrorResponse {
+    #[inline]
+    fn from(value: ChatStreamError) -> Self {
+        Self {
+            name: value.name,
+            message: value.message,
+            status: StatusCode::OK,
+        }
+    }
+}
+
+/// Streaming chat message that could not be decoded.
+#[derive(Debug, thiserror::Error)]
+#[error("invalid message: {source}")]
+pub struct DecodeError {
+    /// Id of the [`ChatStreamRequest`], if the message has one.
+    pub id: Option<String>,
+    pub source: serde_json::Error,
+}
+
+impl DecodeError {
+    /// Creates a new [`DecodeError`] of the message without an id.
+    pub(crate) const fn new(source: serde_json::Error) -> Self {
+        Self { id: None, source }
+    }
+}
+
+/// Wire format of the [`ChatStreamMessage`], with either a chunk or an error.
+mod wire {
+    use serde::{Deserialize, Serialize};
+
+    use crate::lang::chat::{ChatMessage, FinishReason, Metadata, Timestamp, TokenUsage};
+    use crate::lang::chat_stream::{
+        ChatStreamChunk, ChatStreamEnd, ChatStreamError, ChatStreamEvent, ChatStreamMessage,
+    };
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Message {
+        id: String,
+        created_at: Timestamp,
+        router_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        metadata: Option<Metadata>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        chunk: Option<Chunk>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<ChatStreamError>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Chunk {
+        model_id: String,
+        model_name: String,
+        provider_id: String,
+        #[serde(default)]
+        cached: bool,
+        model_response: ModelResponse,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        finish_reason: Option<FinishReason>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct ModelResponse {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        metadata: Option<Metadata>,
+        message: ChatMessage,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token_count: Option<TokenUsage>,
+    }
+
+    impl TryFrom<Message> for ChatStreamMessage {
+        type Error = String;
+
+        fn try_from(x: Message) -> Result<Self, Self::Error> {
+            let event = match (x.chunk, x.error) {
+                (_, Some(error)) => ChatStreamEvent::Error(error),
+                (Some(chunk), None) => {
+                    let Chunk {
+                        model_response: response,
+                        finish_reason,
+                        ..
+                    } = chunk;
+                    let inner = ChatStreamChunk {
+                        model_id: chunk.model_id,
+                        model_name: chunk.model_name,
+                        provider_id: chunk.provider_id,
+                        cached: chunk.cached,
+                        message: response.message,
+                        metadata: response.metadata,
+                    };
+
+                    match finish_reason {
+                        Some(finish_reason) => ChatStreamEvent::End(ChatStreamEnd {
+                            chunk: inner,
+                            finish_reason,
+                            token_usage: response.token_count,
+                        }),
+                        None => ChatStreamEvent::Chunk(inner),
+                    }
+                }
+                (None, None) => return Err(format!("message `{}` has no chunk", x.id)),
+            };
+
+            Ok(Self {
+                id: x.id,
+                created_at: x.created_at,
+                router_id: x.router_id,
+                metadata: x.metadata,
+                event,
+            })
+        }
+    }
+
+    impl From<ChatStreamMessage> for Message {
+        fn from(x: ChatStreamMessage) -> Self {
+            let chunk = |x: ChatStreamChunk, finish_reason, token_count| Chunk {
+                model_id: x.model_id,
+                model_name: x.model_name,
+                provider_id: x.provider_id,
+                cached: x.cached,
+                model_response: ModelResponse {
+                    metadata: x.metadata,
+                    message: x.message,
+                    token_count,
+                },
+                finish_reason,
+            };
+
+            let (chunk, error) = match x.event {
+                ChatStreamEvent::Chunk(x) => (Some(chunk(x, None, None)), None),
+                ChatStreamEvent::End(x) => {
+                    let finish_reason = Some(x.finish_reason);
+                    (Some(chunk(x.chunk, finish_reason, x.token_usage)), None)
+                }
+                ChatStreamEvent::Error(x) => (None, Some(x)),
+            };
+
+            Self {
+                id: x.id,
+                created_at: x.created_at,
+                router_id: x.router_id,
+                metadata: x.metadata,
+                chunk,
+                error,
+            }
+        }
+    }
+}
+
+impl fmt::Display for ChatStreamMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.event {
+            ChatStreamEvent::Error(x) => write!(f, "{x}"),
+            _ => f.write_str(&self.content()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::lang::chat::{ChatMessage, FinishReason};
+    use crate::lang::chat_stream::{ChatStreamEvent, ChatStreamMessage, ChatStreamRequest};
+
+    #[test]
+    fn request() {
+        let a = ChatStreamRequest::new("Hello!");
+        let b = ChatStreamRequest::new("Hello!").with_history(vec![ChatMessage::new("Hi!")]);
+        assert_ne!(a.id, b.id);
+
+        let value = serde_json::to_value(a.with_id("1")).unwrap();
+        let expected = json!({
+            "id": "1",
+            "message": { "content": "Hello!", "role": null },
+            "message_history": [],
+        });
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn message() {
+        let chunk = json!({
+            "id": "1",
+            "created_at": 1700000000,
+            "router_id": "myrouter",
+            "chunk": {
+                "model_id": "gpt",
+                "model_name": "gpt-4o",
+                "provider_id": "openai",
+                "cached": false,
+                "model_response": { "message": { "role": "assistant", "content": "Hi" } },
+            },
+        });
+
+        let message: ChatStreamMessage = serde_json::from_value(chunk.clone()).unwrap();
+        assert_eq!(message.content(), "Hi");
+        assert!(!message.is_final());
+        assert_eq!(serde_json::to_value(&message).unwrap(), chunk);
+
+        let mut end = chunk;
+        end["chunk"]["finish_reason"] = json!("max_tokens");
+        end["chunk"]["model_response"]["token_count"] =
+            json!({ "prompt_tokens": 2, "response_tokens": 1, "total_tokens": 3 });
+        let message: ChatStreamMessage = serde_json::from_value(end.clone()).unwrap();
+        let ChatStreamEvent::End(x) = &message.event else {
+            panic!("should be the end of the stream");
+        };
+
+        assert_eq!(x.finish_reason, FinishReason::MaxTokens);
+        assert_eq!(x.token_usage.unwrap().total_tokens, 3);
+        assert_eq!(serde_json::to_value(&message).unwrap(), end);
+
+        let error = json!({
+            "id": "1",
+            "created_at": 1700000000,
+            "router_id": "myrouter",
+            "error": { "name": "no_models_available", "message": "no models" },
+        });
+
+        let message: ChatStreamMessage = serde_json::from_value(error).unwrap();
+        assert!(message.is_final());
+        assert_eq!(message.to_string(), "no_models_available: no models");
+    }
+}
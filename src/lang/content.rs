@@ -0,0 +1,368 @@
+//! Multimodal content of [`ChatMessage`]s.
+//!
+//! [`ChatMessage`]: crate::lang::chat::ChatMessage
+
+use std::borrow::Cow;
+use std::io;
+use std::path::Path;
+
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+
+/// The content of the message: either plain text or a list of parts.
+///
+/// Serialized as a `JSON` string or an array of typed parts respectively.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged, from = "ContentRepr")]
+pub enum Content {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+impl Content {
+    /// Returns the text without allocating, same as [`Content::text`].
+    ///
+    /// Returns `None` if the content has more than one text part.
+    #[must_use]
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Self::Text(x) => Some(x.as_str()),
+            Self::Parts(x) => {
+                let mut parts = x.iter().filter_map(ContentPart::as_text);
+                match (parts.next(), parts.next()) {
+                    (x, None) => Some(x.unwrap_or_default()),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Returns all text parts joined by a newline.
+    #[must_use]
+    pub fn text(&self) -> Cow<'_, str> {
+        match self {
+            Self::Text(x) => Cow::Borrowed(x.as_str()),
+            Self::Parts(x) => {
+                let parts: Vec<_> = x.iter().filter_map(ContentPart::as_text).collect();
+                Cow::Owned(parts.join("\n"))
+            }
+        }
+    }
+
+    /// Returns all parts, with the plain text as a single [`ContentPart::Text`].
+    #[must_use]
+    pub fn parts(&self) -> Cow<'_, [ContentPart]> {
+        match self {
+            Self::Text(x) => Cow::Owned(vec![ContentPart::Text(x.clone())]),
+            Self::Parts(x) => Cow::Borrowed(x.as_slice()),
+        }
+    }
+}
+
+impl Default for Content {
+    #[inline]
+    fn default() -> Self {
+        Self::Text(String::new())
+    }
+}
+
+impl From<String> for Content {
+    #[inline]
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl From<&str> for Content {
+    #[inline]
+    fn from(value: &str) -> Self {
+        Self::Text(value.to_owned())
+    }
+}
+
+impl From<Vec<ContentPart>> for Content {
+    #[inline]
+    fn from(value: Vec<ContentPart>) -> Self {
+        Self::Parts(value)
+    }
+}
+
+impl PartialEq<str> for Content {
+    fn eq(&self, other: &str) -> bool {
+        matches!(self, Self::Text(x) if x == other)
+    }
+}
+
+impl PartialEq<&str> for Content {
+    #[inline]
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+/// Accepts `null` content, e.g. of replies with tool calls only.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ContentRepr {
+    Text(String),
+    Parts(Vec<ContentPart>),
+    Null,
+}
+
+impl From<ContentRepr> for Content {
+    fn from(value: ContentRepr) -> Self {
+        match value {
+            ContentRepr::Text(x) => Self::Text(x),
+            ContentRepr::Parts(x) => Self::Parts(x),
+            ContentRepr::Null => Self::default(),
+        }
+    }
+}
+
+/// The level of detail of the image.
+#[must_use]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImageDetail {
+    #[serde(rename = "auto")]
+    Auto,
+    #[serde(rename = "low")]
+    Low,
+    #[serde(rename = "high")]
+    High,
+}
+
+/// A single part of the multimodal content.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "PartRepr", into = "PartRepr")]
+pub enum ContentPart {
+    /// Plain text.
+    Text(String),
+    /// Image referenced by the `URL`.
+    ImageUrl {
+        url: String,
+        detail: Option<ImageDetail>,
+    },
+    /// Base64-encoded image with its media type, e.g. `image/png`.
+    ///
+    /// Serialized as an image with the `data:` `URL`.
+    Image {
+        media_type: String,
+        data: String,
+        detail: Option<ImageDetail>,
+    },
+}
+
+impl ContentPart {
+    /// Creates a new [`ContentPart::Text`].
+    #[inline]
+    #[must_use]
+    pub fn text(text: &str) -> Self {
+        Self::Text(text.to_owned())
+    }
+
+    /// Creates a new [`ContentPart::ImageUrl`].
+    #[inline]
+    #[must_use]
+    pub fn image_url(url: &str) -> Self {
+        Self::ImageUrl {
+            url: url.to_owned(),
+            detail: None,
+        }
+    }
+
+    /// Creates a new [`ContentPart::Image`] by encoding the raw image bytes.
+    #[must_use]
+    pub fn image_bytes(media_type: &str, bytes: &[u8]) -> Self {
+        Self::Image {
+            media_type: media_type.to_owned(),
+            data: base64::engine::general_purpose::STANDARD.encode(bytes),
+            detail: None,
+        }
+    }
+
+    /// Creates a new [`ContentPart::Image`] from the image file.
+    ///
+    /// The media type is inferred from the file extension
+    /// (`png`, `jpg`, `jpeg`, `gif` or `webp`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the file cannot be read, has an unsupported
+    /// extension or is larger than `max_bytes`.
+    ///
+    /// [`Error`]: crate::Error
+    pub fn image_file(path: impl AsRef<Path>, max_bytes: u64) -> Result<Self> {
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|x| x.to_str());
+        let media_type = match extension.map(str::to_ascii_lowercase).as_deref() {
+            Some("png") => "image/png",
+            Some("jpg" | "jpeg") => "image/jpeg",
+            Some("gif") => "image/gif",
+            Some("webp") => "image/webp",
+            _ => {
+                let error = format!("unsupported image extension: {}", path.display());
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, error).into());
+            }
+        };
+
+        let size = std::fs::metadata(path)?.len();
+        if size > max_bytes {
+            let error = format!("image is {size} bytes, the limit is {max_bytes} bytes");
+            return Err(io::Error::new(io::ErrorKind::InvalidData, error).into());
+        }
+
+        let bytes = std::fs::read(path)?;
+        Ok(Self::image_bytes(media_type, &bytes))
+    }
+
+    /// Overrides the default [`ImageDetail`] of the image.
+    ///
+    /// Has no effect on [`ContentPart::Text`].
+    #[must_use]
+    pub fn with_detail(mut self, level: ImageDetail) -> Self {
+        if let Self::ImageUrl { detail, .. } | Self::Image { detail, .. } = &mut self {
+            *detail = Some(level);
+        }
+
+        self
+    }
+
+    /// Returns the text of the [`ContentPart::Text`].
+    #[inline]
+    #[must_use]
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Self::Text(x) => Some(x.as_str()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum PartRepr {
+    #[serde(rename = "text")]
+    Text { text: String },
+    #[serde(rename = "image_url")]
+    ImageUrl { image_url: ImageRepr },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct ImageRepr {
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<ImageDetail>,
+}
+
+impl From<PartRepr> for ContentPart {
+    fn from(value: PartRepr) -> Self {
+        let ImageRepr { url, detail } = match value {
+            PartRepr::Text { text } => return Self::Text(text),
+            PartRepr::ImageUrl { image_url } => image_url,
+        };
+
+        let data_url = url
+            .strip_prefix("data:")
+            .and_then(|x| x.split_once(";base64,"));
+        match data_url {
+            Some((media_type, data)) => Self::Image {
+                media_type: media_type.to_owned(),
+                data: data.to_owned(),
+                detail,
+            },
+            None => Self::ImageUrl { url, detail },
+        }
+    }
+}
+
+impl From<ContentPart> for PartRepr {
+    fn from(value: ContentPart) -> Self {
+        let image_url = match value {
+            ContentPart::Text(text) => return Self::Text { text },
+            ContentPart::ImageUrl { url, detail } => ImageRepr { url, detail },
+            ContentPart::Image {
+                media_type,
+                data,
+                detail,
+            } => ImageRepr {
+                url: format!("data:{media_type};base64,{data}"),
+                detail,
+            },
+        };
+
+        Self::ImageUrl { image_url }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::fixtures::TempDir;
+    use crate::lang::content::{Content, ContentPart, ImageDetail};
+
+    #[test]
+    fn serialize() {
+        let content = Content::from(vec![
+            ContentPart::text("What is this?"),
+            ContentPart::image_url("https://example.com/a.png").with_detail(ImageDetail::Low),
+            ContentPart::image_bytes("image/png", b"png"),
+        ]);
+
+        let value = serde_json::to_value(&content).unwrap();
+        assert_eq!(
+            value,
+            json!([
+                { "type": "text", "text": "What is this?" },
+                { "type": "image_url", "image_url": { "url": "https://example.com/a.png", "detail": "low" } },
+                { "type": "image_url", "image_url": { "url": "data:image/png;base64,cG5n" } },
+            ])
+        );
+
+        let content: Content = serde_json::from_value(value).unwrap();
+        let Content::Parts(parts) = content else {
+            panic!("should be parts");
+        };
+
+        assert!(
+            matches!(&parts[2], ContentPart::Image { media_type, data, .. }
+            if media_type == "image/png" && data == "cG5n")
+        );
+    }
+
+    #[test]
+    fn deserialize() {
+        let content: Content = serde_json::from_value(json!("Hello!")).unwrap();
+        assert_eq!(content, "Hello!");
+
+        let content: Content = serde_json::from_value(json!(null)).unwrap();
+        assert_eq!(content, "");
+    }
+
+    #[test]
+    fn text() {
+        let content = Content::from(vec![
+            ContentPart::image_url("https://example.com/a.png"),
+            ContentPart::text("Hi!"),
+        ]);
+        assert_eq!(content.as_text(), Some("Hi!"));
+        assert_eq!(content.text(), "Hi!");
+
+        let content = Content::from(vec![ContentPart::text("Hi!"), ContentPart::text("Bye!")]);
+        assert_eq!(content.as_text(), None);
+        assert_eq!(content.text(), "Hi!\nBye!");
+    }
+
+    #[test]
+    fn image_file() {
+        let dir = TempDir::new("image");
+        let path = dir.path().join("image.png");
+        std::fs::write(&path, b"png").unwrap();
+
+        assert!(ContentPart::image_file(&path, 3).is_ok());
+        assert!(ContentPart::image_file(&path, 2).is_err());
+        assert!(ContentPart::image_file("image.bmp", 3).is_err());
+    }
+}
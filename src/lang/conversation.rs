@@ -0,0 +1,221 @@
+//! Multi-turn chat with the message history kept between requests.
+
+use crate::lang::chat::{ChatMessage, ChatRequest, ChatResponse, Role};
+use crate::lang::Language;
+use crate::Result;
+
+/// Multi-turn chat with an automatically managed message history.
+///
+/// #### Example
+///
+/// ```rust,no_run
+/// use glide_rs::lang::Conversation;
+/// use glide_rs::Client;
+///
+/// # let _ = async {
+/// let glide = Client::default();
+/// let mut conversation = Conversation::new().with_system_prompt("Be concise.");
+/// let _ = conversation.send(&glide.lang, "myrouter", "Hello!").await?;
+/// let _ = conversation.send(&glide.lang, "myrouter", "How are you?").await?;
+/// # Ok::<_, glide_rs::Error>(())
+/// # };
+/// ```
+#[must_use]
+#[derive(Debug, Clone, Default)]
+pub struct Conversation {
+    system: Option<ChatMessage>,
+    history: Vec<ChatMessage>,
+}
+
+impl Conversation {
+    /// Creates a new empty [`Conversation`].
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            system: None,
+            history: Vec::new(),
+        }
+    }
+
+    /// Attaches the system prompt, sent before every message history.
+    pub fn with_system_prompt(mut self, prompt: &str) -> Self {
+        self.system = Some(ChatMessage::new(prompt).with_system());
+        self
+    }
+
+    /// Returns the reference to the system prompt.
+    #[inline]
+    #[must_use]
+    pub const fn system_prompt(&self) -> Option<&ChatMessage> {
+        self.system.as_ref()
+    }
+
+    /// Returns the message history, excluding the system prompt.
+    #[inline]
+    pub fn history(&self) -> &[ChatMessage] {
+        &self.history
+    }
+
+    /// Removes all messages, keeping the system prompt.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+
+    /// Creates a new [`ChatRequest`] for the provided message,
+    /// carrying the system prompt and the message history.
+    pub fn request(&self, message: impl Into<ChatMessage>) -> ChatRequest {
+        let history: Vec<_> = self
+            .system
+            .iter()
+            .chain(self.history.iter())
+            .cloned()
+            .collect();
+
+        let mut request = ChatRequest::new(message);
+        if !history.is_empty() {
+            request.message_history = Some(history);
+        }
+
+        request
+    }
+
+    /// Sends the message to a specified `router` and appends both
+    /// the message and the [`Role::Assistant`] reply to the history.
+    ///
+    /// The history is left untouched if the request fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the response [`StatusCode`] is not in the 200-299 range.
+    ///
+    /// [`Error`]: crate::Error
+    /// [`StatusCode`]: reqwest::StatusCode
+    pub async fn send(
+        &mut self,
+        lang: &Language,
+        router: &str,
+        message: impl Into<ChatMessage>,
+    ) -> Result<ChatResponse> {
+        let message = message.into();
+        let message = match message.role {
+            Some(_) => message,
+            None => message.with_role(Role::User),
+        };
+
+        let request = self.request(message.clone());
+        let response = lang.chat(router, request).await?;
+
+        let reply = response.model_response.message.clone();
+        self.history.push(message);
+        self.history.push(reply.with_role(Role::Assistant));
+
+        Ok(response)
+    }
+
+    /// Removes the last turn, i.e. the last [`Role::Assistant`] reply
+    /// and every message sent after the reply before it.
+    ///
+    /// Returns the removed message that started the turn.
+    pub fn undo(&mut self) -> Option<ChatMessage> {
+        if self.history.last()?.role() == Role::Assistant {
+            self.history.pop();
+        }
+
+        let start = self
+            .history
+            .iter()
+            .rposition(|x| x.role() == Role::Assistant)
+            .map_or(0, |x| x + 1);
+
+        let mut removed = self.history.split_off(start);
+        match removed.len() {
+            0 => None,
+            _ => Some(removed.swap_remove(0)),
+        }
+    }
+
+    /// Removes the last [`Role::Assistant`] reply and resends the message
+    /// that started the turn.
+    ///
+    /// Returns `None` if the history is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the response [`StatusCode`] is not in the 200-299 range.
+    /// The removed turn is restored in that case.
+    ///
+    /// [`Error`]: crate::Error
+    /// [`StatusCode`]: reqwest::StatusCode
+    pub async fn regenerate(
+        &mut self,
+        lang: &Language,
+        router: &str,
+    ) -> Result<Option<ChatResponse>> {
+        let reply = match self.history.last() {
+            None => return Ok(None),
+            Some(x) if x.role() == Role::Assistant => self.history.pop(),
+            Some(_) => None,
+        };
+
+        let Some(message) = self.history.pop() else {
+            self.history.extend(reply);
+            return Ok(None);
+        };
+
+        match self.send(lang, router, message.clone()).await {
+            Ok(response) => Ok(Some(response)),
+            Err(error) => {
+                self.history.push(message);
+                self.history.extend(reply);
+                Err(error)
+            }
+        }
+    }
+}
+
+impl Extend<ChatMessage> for Conversation {
+    fn extend<T: IntoIterator<Item = ChatMessage>>(&mut self, iter: T) {
+        self.history.extend(iter);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lang::chat::{ChatMessage, Role};
+    use crate::lang::Conversation;
+
+    #[test]
+    fn request() {
+        let mut conversation = Conversation::new().with_system_prompt("Be concise.");
+        conversation.extend([
+            ChatMessage::new("Hello!").with_role(Role::User),
+            ChatMessage::new("Hi!").with_role(Role::Assistant),
+        ]);
+
+        let request = conversation.request("How are you?");
+        let history = request.message_history.unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].role(), Role::System);
+        assert_eq!(request.message.content, "How are you?");
+    }
+
+    #[test]
+    fn undo() {
+        let mut conversation = Conversation::new();
+        conversation.extend([
+            ChatMessage::new("Hello!").with_role(Role::User),
+            ChatMessage::new("Hi!").with_role(Role::Assistant),
+            ChatMessage::new("How are you?").with_role(Role::User),
+            ChatMessage::new("Fine.").with_role(Role::Assistant),
+        ]);
+
+        let removed = conversation.undo().unwrap();
+        assert_eq!(removed.content, "How are you?");
+        assert_eq!(conversation.history().len(), 2);
+
+        let removed = conversation.undo().unwrap();
+        assert_eq!(removed.content, "Hello!");
+        assert!(conversation.undo().is_none());
+    }
+}
@@ -0,0 +1,448 @@
+//! Cost accounting of [`ChatResponse`]s from their [`TokenUsage`].
+//!
+//! All prices and costs are in `USD`.
+
+use std::collections::HashMap;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign};
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::{fs, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::lang::chat::{ChatResponse, TokenUsage};
+use crate::Result;
+
+/// Price of the model in `USD` per million tokens.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Price {
+    /// Price of a million prompt tokens.
+    pub input: f64,
+    /// Price of a million response tokens.
+    pub output: f64,
+}
+
+impl Price {
+    /// Creates a new [`Price`] in `USD` per million tokens.
+    #[inline]
+    #[must_use]
+    pub const fn new(input: f64, output: f64) -> Self {
+        Self { input, output }
+    }
+
+    /// Returns the [`Cost`] of the [`TokenUsage`].
+    ///
+    /// Negative token counts are treated as zero.
+    #[must_use]
+    pub fn cost(&self, usage: &TokenUsage) -> Cost {
+        let tokens = |x: i32| f64::from(x.max(0)) / 1e6;
+        Cost {
+            input: self.input * tokens(usage.prompt_tokens),
+            output: self.output * tokens(usage.response_tokens),
+        }
+    }
+}
+
+/// Cost of one or more chat requests in `USD`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Cost {
+    /// Cost of the prompt tokens.
+    pub input: f64,
+    /// Cost of the response tokens.
+    pub output: f64,
+}
+
+impl Cost {
+    /// Returns the total cost.
+    #[inline]
+    #[must_use]
+    pub fn total(&self) -> f64 {
+        self.input + self.output
+    }
+}
+
+impl Add for Cost {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            input: self.input + rhs.input,
+            output: self.output + rhs.output,
+        }
+    }
+}
+
+impl AddAssign for Cost {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sum for Cost {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+/// Prices of models, keyed by the provider and the model name.
+///
+/// Serialized as `{"openai": {"gpt-4o": {"input": 2.5, "output": 10.0}}}`.
+/// The model name `*` matches all models of the provider.
+///
+/// [`Pricing::default`] contains the list prices of popular models.
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Pricing(pub HashMap<String, HashMap<String, Price>>);
+
+impl Pricing {
+    /// Creates a new empty [`Pricing`].
+    #[inline]
+    pub fn new() -> Self {
+        Self(HashMap::new())
+    }
+
+    /// Loads the [`Pricing`] from the `JSON` file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the file cannot be read or is not a valid pricing table.
+    ///
+    /// [`Error`]: crate::Error
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let pricing = serde_json::from_str(&content)
+            .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x))?;
+        Ok(pricing)
+    }
+
+    /// Sets the [`Price`] of the `model` of the `provider`.
+    pub fn insert(&mut self, provider: &str, model: &str, price: Price) {
+        let models = self.0.entry(provider.to_owned()).or_default();
+        models.insert(model.to_owned(), price);
+    }
+
+    /// Adds all prices of the other [`Pricing`], replacing the existing ones.
+    pub fn merge(&mut self, other: Self) {
+        for (provider, models) in other.0 {
+            self.0.entry(provider).or_default().extend(models);
+        }
+    }
+
+    /// Returns the [`Price`] of the `model` of the `provider`.
+    ///
+    /// Versioned model names, e.g. `gpt-4o-2024-08-06`, fall back to
+    /// the longest listed prefix followed by `-`, e.g. `gpt-4o`, and then to `*`.
+    #[must_use]
+    pub fn get(&self, provider: &str, model: &str) -> Option<Price> {
+        let models = self.0.get(provider)?;
+        if let Some(price) = models.get(model) {
+            return Some(*price);
+        }
+
+        let prefix = models
+            .iter()
+            .filter(|(name, _)| {
+                let suffix = model.strip_prefix(name.as_str());
+                suffix.is_some_and(|x| x.starts_with('-'))
+            })
+            .max_by_key(|(name, _)| name.len());
+        prefix
+            .map(|(_, price)| *price)
+            .or_else(|| models.get("*").copied())
+    }
+
+    /// Returns the [`Cost`] of the [`ChatResponse`], if its model is priced.
+    ///
+    /// Cached responses are free, whether their model is priced or not.
+    #[must_use]
+    pub fn cost(&self, response: &ChatResponse) -> Option<Cost> {
+        if response.cached {
+            return Some(Cost::default());
+        }
+
+        let price = self.get(&response.provider_id, &response.model_name)?;
+        Some(price.cost(&response.model_response.token_count))
+    }
+}
+
+impl Default for Pricing {
+    /// Creates a new [`Pricing`] with the list prices of popular models.
+    fn default() -> Self {
+        let openai = [
+            ("gpt-4o", Price::new(2.5, 10.0)),
+            ("gpt-4o-mini", Price::new(0.15, 0.6)),
+            ("gpt-4-turbo", Price::new(10.0, 30.0)),
+            ("gpt-4", Price::new(30.0, 60.0)),
+            ("gpt-3.5-turbo", Price::new(0.5, 1.5)),
+        ];
+
+        let anthropic = [
+            ("claude-3-5-sonnet", Price::new(3.0, 15.0)),
+            ("claude-3-5-haiku", Price::new(0.8, 4.0)),
+            ("claude-3-opus", Price::new(15.0, 75.0)),
+            ("claude-3-sonnet", Price::new(3.0, 15.0)),
+            ("claude-3-haiku", Price::new(0.25, 1.25)),
+        ];
+
+        let cohere = [
+            ("command-r-plus", Price::new(2.5, 10.0)),
+            ("command-r", Price::new(0.15, 0.6)),
+            ("command-light", Price::new(0.3, 0.6)),
+            ("command", Price::new(1.0, 2.0)),
+        ];
+
+        let bedrock = [
+            ("amazon.titan-text-lite", Price::new(0.15, 0.2)),
+            ("amazon.titan-text-express", Price::new(0.2, 0.6)),
+        ];
+
+        let ollama = [("*", Price::new(0.0, 0.0))];
+
+        let mut pricing = Self::new();
+        let providers: [(&str, &[(&str, Price)]); 6] = [
+            ("openai", &openai),
+            ("azureopenai", &openai),
+            ("anthropic", &anthropic),
+            ("cohere", &cohere),
+            ("bedrock", &bedrock),
+            ("ollama", &ollama),
+        ];
+
+        for (provider, models) in providers {
+            for (model, price) in models {
+                pricing.insert(provider, model, *price);
+            }
+        }
+
+        pricing
+    }
+}
+
+/// Aggregated usage and [`Cost`] of chat requests.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Spend {
+    /// Number of recorded responses.
+    pub requests: u64,
+    /// Number of recorded responses of models without a [`Price`].
+    pub unpriced: u64,
+    pub prompt_tokens: u64,
+    pub response_tokens: u64,
+    pub cost: Cost,
+}
+
+impl Spend {
+    fn record(&mut self, usage: &TokenUsage, cost: Option<Cost>) {
+        let tokens = |x: i32| u64::try_from(x).unwrap_or_default();
+        self.requests += 1;
+        self.unpriced += u64::from(cost.is_none());
+        self.prompt_tokens += tokens(usage.prompt_tokens);
+        self.response_tokens += tokens(usage.response_tokens);
+        self.cost += cost.unwrap_or_default();
+    }
+}
+
+#[derive(Debug, Default)]
+struct Ledger {
+    total: Spend,
+    routers: HashMap<String, Spend>,
+    models: HashMap<String, Spend>,
+    tags: HashMap<String, Spend>,
+}
+
+/// Aggregates the [`Spend`] of [`ChatResponse`]s by `router`, model and tag.
+///
+/// Cheap to clone, all clones share the same records. Once attached with
+/// [`Builder::with_cost_tracker`], all chat responses are recorded with
+/// the tag of the [`Router`], see [`Router::with_tag`].
+///
+/// [`Builder::with_cost_tracker`]: crate::Builder::with_cost_tracker
+/// [`Router`]: crate::lang::Router
+/// [`Router::with_tag`]: crate::lang::Router::with_tag
+#[must_use]
+#[derive(Debug, Clone, Default)]
+pub struct CostTracker {
+    pricing: Arc<Pricing>,
+    ledger: Arc<Mutex<Ledger>>,
+}
+
+impl CostTracker {
+    /// Creates a new [`CostTracker`] with the specified [`Pricing`].
+    pub fn new(pricing: Pricing) -> Self {
+        Self {
+            pricing: Arc::new(pricing),
+            ledger: Arc::default(),
+        }
+    }
+
+    /// Returns the [`Pricing`] used to compute costs.
+    #[inline]
+    pub fn pricing(&self) -> &Pricing {
+        &self.pricing
+    }
+
+    /// Records the [`ChatResponse`] with the optional custom `tag`.
+    ///
+    /// Returns its [`Cost`], if its model is priced.
+    pub fn record(&self, response: &ChatResponse, tag: Option<&str>) -> Option<Cost> {
+        let cost = self.pricing.cost(response);
+        let usage = &response.model_response.token_count;
+
+        let mut ledger = self.lock();
+        ledger.total.record(usage, cost);
+
+        let router = ledger.routers.entry(response.router_id.clone());
+        router.or_default().record(usage, cost);
+        let model = ledger.models.entry(response.model_name.clone());
+        model.or_default().record(usage, cost);
+        if let Some(tag) = tag {
+            let tag = ledger.tags.entry(tag.to_owned());
+            tag.or_default().record(usage, cost);
+        }
+
+        cost
+    }
+
+    /// Returns the [`Spend`] of all recorded responses.
+    #[must_use]
+    pub fn total(&self) -> Spend {
+        self.lock().total
+    }
+
+    /// Returns the [`Spend`] of the specified `router`.
+    #[must_use]
+    pub fn by_router(&self, router: &str) -> Spend {
+        self.lock().routers.get(router).copied().unwrap_or_default()
+    }
+
+    /// Returns the [`Spend`] of the specified model name.
+    #[must_use]
+    pub fn by_model(&self, model: &str) -> Spend {
+        self.lock().models.get(model).copied().unwrap_or_default()
+    }
+
+    /// Returns the [`Spend`] of the specified custom tag.
+    #[must_use]
+    pub fn by_tag(&self, tag: &str) -> Spend {
+        self.lock().tags.get(tag).copied().unwrap_or_default()
+    }
+
+    /// Returns the [`Spend`] of all `router`s.
+    #[must_use]
+    pub fn routers(&self) -> HashMap<String, Spend> {
+        self.lock().routers.clone()
+    }
+
+    /// Returns the [`Spend`] of all models.
+    #[must_use]
+    pub fn models(&self) -> HashMap<String, Spend> {
+        self.lock().models.clone()
+    }
+
+    /// Returns the [`Spend`] of all custom tags.
+    #[must_use]
+    pub fn tags(&self) -> HashMap<String, Spend> {
+        self.lock().tags.clone()
+    }
+
+    /// Removes all records.
+    pub fn reset(&self) {
+        *self.lock() = Ledger::default();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Ledger> {
+        self.ledger.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::fixtures::{self, TempDir};
+    use crate::lang::chat::ChatResponse;
+    use crate::lang::cost::{CostTracker, Price, Pricing};
+
+    fn response(router: &str, provider: &str, model: &str) -> ChatResponse {
+        let mut response = fixtures::response(1_000_000, 500_000);
+        response.router_id = router.to_owned();
+        response.provider_id = provider.to_owned();
+        response.model_name = model.to_owned();
+        response
+    }
+
+    #[test]
+    fn pricing() {
+        let pricing = Pricing::default();
+        assert_eq!(pricing.get("openai", "gpt-4o"), Some(Price::new(2.5, 10.0)));
+        assert_eq!(
+            pricing.get("openai", "gpt-4o-mini-2024-07-18"),
+            Some(Price::new(0.15, 0.6))
+        );
+        assert_eq!(pricing.get("ollama", "llama3"), Some(Price::new(0.0, 0.0)));
+        assert_eq!(pricing.get("openai", "davinci"), None);
+        assert_eq!(pricing.get("openai", "gpt-4.1"), None);
+        assert_eq!(
+            pricing.get("openai", "gpt-4-0613"),
+            Some(Price::new(30.0, 60.0))
+        );
+
+        let cost = pricing.cost(&response("a", "openai", "gpt-4o")).unwrap();
+        assert!((cost.total() - 7.5).abs() < 1e-9);
+
+        let dir = TempDir::new("pricing");
+        let path = dir.path().join("pricing.json");
+        std::fs::write(
+            &path,
+            r#"{"openai": {"davinci": {"input": 1, "output": 2}}}"#,
+        )
+        .unwrap();
+        let mut pricing = Pricing::default();
+        pricing.merge(Pricing::from_file(&path).unwrap());
+        assert_eq!(pricing.get("openai", "davinci"), Some(Price::new(1.0, 2.0)));
+    }
+
+    #[test]
+    fn prefix() {
+        let pricing: Pricing = serde_json::from_value(json!({
+            "openai": { "gpt-4": { "input": 30, "output": 60 } },
+        }))
+        .unwrap();
+
+        assert_eq!(pricing.get("openai", "gpt-4o"), None);
+        assert_eq!(pricing.get("openai", "gpt-4.1"), None);
+        assert_eq!(
+            pricing.get("openai", "gpt-4-turbo"),
+            Some(Price::new(30.0, 60.0))
+        );
+    }
+
+    #[test]
+    fn tracker() {
+        let tracker = CostTracker::default();
+        tracker.record(&response("a", "openai", "gpt-4o"), Some("search"));
+        tracker.record(&response("a", "anthropic", "claude-3-haiku"), None);
+        tracker.record(&response("b", "octoml", "llama-2"), Some("search"));
+
+        let mut cached = response("a", "openai", "gpt-4o");
+        cached.cached = true;
+        let cost = tracker.record(&cached, None).unwrap();
+        assert_eq!(cost.total(), 0.0);
+
+        let total = tracker.total();
+        assert_eq!((total.requests, total.unpriced), (4, 1));
+        assert_eq!(total.prompt_tokens, 4_000_000);
+        assert!((total.cost.total() - 8.375).abs() < 1e-9);
+
+        assert_eq!(tracker.by_router("a").requests, 3);
+        assert_eq!(tracker.by_model("gpt-4o").requests, 2);
+        assert_eq!(tracker.by_tag("search").requests, 2);
+        assert_eq!(tracker.by_tag("chat").requests, 0);
+
+        tracker.clone().reset();
+        assert_eq!(tracker.total().requests, 0);
+    }
+}
@@ -0,0 +1,381 @@
+//! Adapters of content deltas of a streamed reply, e.g. for `HTTP` responses,
+//! terminals or text-to-speech engines.
+//!
+//! #### Example
+//!
+//! ```rust,no_run
+//! use futures::{SinkExt, StreamExt};
+//! use glide_rs::lang::aggregate::ChatStreamExt;
+//! use glide_rs::lang::chat_stream::ChatStreamRequest;
+//! use glide_rs::lang::delta::{Boundary, TextStreamExt};
+//! use glide_rs::Client;
+//!
+//! # #[cfg(feature = "streaming")]
+//! # let _ = async {
+//! let glide = Client::default();
+//! let mut chat = glide.lang.stream("myrouter").await?;
+//! chat.send(ChatStreamRequest::new("Hello!")).await?;
+//!
+//! let mut sentences = chat.deltas().rechunk(Boundary::Sentence);
+//! while let Some(sentence) = sentences.next().await {
+//!     println!("{}", sentence?);
+//! }
+//! # Ok::<_, glide_rs::Error>(())
+//! # };
+//! ```
+
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::lang::aggregate::{Aggregate, AggregateEvent};
+use crate::lang::chat_stream::ChatStreamMessage;
+use crate::{Error, Result};
+
+/// Stream of content deltas, created with [`ChatStreamExt::deltas`].
+///
+/// Skips empty deltas and ends after the end-of-stream message.
+///
+/// [`ChatStreamExt::deltas`]: crate::lang::aggregate::ChatStreamExt::deltas
+#[must_use = "streams do nothing unless you poll them"]
+#[derive(Debug)]
+pub struct Deltas<S> {
+    inner: Aggregate<S>,
+}
+
+impl<S> Deltas<S> {
+    /// Creates a new [`Deltas`] of the reply to the first received request id.
+    #[inline]
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner: Aggregate::new(inner),
+        }
+    }
+
+    /// Returns the content accumulated so far.
+    #[inline]
+    #[must_use]
+    pub fn content(&self) -> &str {
+        self.inner.content()
+    }
+}
+
+impl<S> Stream for Deltas<S>
+where
+    S: Stream<Item = Result<ChatStreamMessage>> + Unpin,
+{
+    type Item = Result<String>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let next = match ready!(self.inner.poll_next_unpin(cx)) {
+                Some(Ok(AggregateEvent::Delta(x))) if !x.content().is_empty() => {
+                    Some(Ok(x.content().into_owned()))
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(x)) => Some(Err(x)),
+                None => None,
+            };
+
+            return Poll::Ready(next);
+        }
+    }
+}
+
+/// Unit of text yielded by the [`Rechunk`] stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Boundary {
+    /// Word with its trailing whitespace.
+    Word,
+    /// Line with its trailing `\n`.
+    Line,
+    /// Sentence ending with `.`, `!`, `?` or `…` and whitespace, or with `\n`.
+    Sentence,
+}
+
+impl Boundary {
+    /// Returns the end of the first complete unit of the text, if any.
+    fn find(&self, text: &str) -> Option<usize> {
+        let mut chars = text.char_indices().peekable();
+        let mut word = false;
+        while let Some((i, x)) = chars.next() {
+            let end = i + x.len_utf8();
+            match self {
+                Self::Word if x.is_whitespace() && word => return Some(end),
+                Self::Word => word |= !x.is_whitespace(),
+                Self::Line | Self::Sentence if x == '\n' => return Some(end),
+                Self::Line => {}
+                Self::Sentence if matches!(x, '.' | '!' | '?' | '…' | '。' | '！' | '？') => {
+                    match chars.peek() {
+                        Some((_, y)) if y.is_whitespace() => return Some(end + y.len_utf8()),
+                        _ if matches!(x, '。' | '！' | '？') => return Some(end),
+                        _ => {}
+                    }
+                }
+                Self::Sentence => {}
+            }
+        }
+
+        None
+    }
+}
+
+/// Stream of text re-chunked at the [`Boundary`], created with [`TextStreamExt::rechunk`].
+///
+/// Yields the incomplete rest of the text once the underlying stream ends.
+#[must_use = "streams do nothing unless you poll them"]
+#[derive(Debug)]
+pub struct Rechunk<S> {
+    inner: S,
+    boundary: Boundary,
+    buffer: String,
+    done: bool,
+}
+
+impl<S> Rechunk<S> {
+    /// Creates a new [`Rechunk`] stream.
+    #[inline]
+    pub fn new(inner: S, boundary: Boundary) -> Self {
+        Self {
+            inner,
+            boundary,
+            buffer: String::new(),
+            done: false,
+        }
+    }
+}
+
+impl<S> Stream for Rechunk<S>
+where
+    S: Stream<Item = Result<String>> + Unpin,
+{
+    type Item = Result<String>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            if let Some(end) = this.boundary.find(&this.buffer) {
+                let rest = this.buffer.split_off(end);
+                let unit = std::mem::replace(&mut this.buffer, rest);
+                return Poll::Ready(Some(Ok(unit)));
+            }
+
+            if this.done {
+                let rest = std::mem::take(&mut this.buffer);
+                return Poll::Ready(Some(rest).filter(|x| !x.is_empty()).map(Ok));
+            }
+
+            match ready!(this.inner.poll_next_unpin(cx)) {
+                Some(Ok(x)) => this.buffer.push_str(&x),
+                Some(Err(x)) => return Poll::Ready(Some(Err(x))),
+                None => this.done = true,
+            }
+        }
+    }
+}
+
+/// Reader of text, created with [`TextStreamExt::into_async_read`].
+///
+/// Implements both `futures::io` and `tokio::io` `AsyncRead` and `AsyncBufRead`.
+#[derive(Debug)]
+pub struct DeltaReader<S> {
+    inner: S,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl<S> DeltaReader<S> {
+    /// Creates a new [`DeltaReader`].
+    #[inline]
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            buffer: Vec::new(),
+            position: 0,
+        }
+    }
+}
+
+impl<S> DeltaReader<S>
+where
+    S: Stream<Item = Result<String>> + Unpin,
+{
+    fn poll_fill(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        while self.position == self.buffer.len() {
+            match ready!(self.inner.poll_next_unpin(cx)) {
+                Some(Ok(x)) => {
+                    self.buffer = x.into_bytes();
+                    self.position = 0;
+                }
+                Some(Err(Error::Io(x))) => return Poll::Ready(Err(x)),
+                Some(Err(x)) => return Poll::Ready(Err(io::Error::other(x))),
+                None => break,
+            }
+        }
+
+        Poll::Ready(Ok(&self.buffer[self.position..]))
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.position = (self.position + amount).min(self.buffer.len());
+    }
+
+    fn poll_copy(&mut self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let available = ready!(self.poll_fill(cx))?;
+        let amount = available.len().min(buf.len());
+        buf[..amount].copy_from_slice(&available[..amount]);
+        self.consume(amount);
+        Poll::Ready(Ok(amount))
+    }
+}
+
+impl<S> futures::io::AsyncRead for DeltaReader<S>
+where
+    S: Stream<Item = Result<String>> + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_copy(cx, buf)
+    }
+}
+
+impl<S> futures::io::AsyncBufRead for DeltaReader<S>
+where
+    S: Stream<Item = Result<String>> + Unpin,
+{
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        self.get_mut().poll_fill(cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amount: usize) {
+        self.get_mut().consume(amount);
+    }
+}
+
+impl<S> tokio::io::AsyncRead for DeltaReader<S>
+where
+    S: Stream<Item = Result<String>> + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let amount = ready!(self.poll_copy(cx, buf.initialize_unfilled()))?;
+        buf.advance(amount);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S> tokio::io::AsyncBufRead for DeltaReader<S>
+where
+    S: Stream<Item = Result<String>> + Unpin,
+{
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        self.get_mut().poll_fill(cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amount: usize) {
+        self.get_mut().consume(amount);
+    }
+}
+
+/// Extension of streams of text, e.g. [`Deltas`] or [`Rechunk`].
+pub trait TextStreamExt: Stream<Item = Result<String>> + Sized {
+    /// Returns the [`Rechunk`] stream of the text at the [`Boundary`].
+    #[inline]
+    fn rechunk(self, boundary: Boundary) -> Rechunk<Self> {
+        Rechunk::new(self, boundary)
+    }
+
+    /// Returns the [`DeltaReader`] of the text.
+    #[inline]
+    fn into_async_read(self) -> DeltaReader<Self> {
+        DeltaReader::new(self)
+    }
+
+    /// Forwards the text to the channel from a new task, see [`forward`].
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the `buffer` is zero or called outside of the `tokio` runtime.
+    fn into_channel(self, buffer: usize) -> mpsc::Receiver<Result<String>>
+    where
+        Self: Send + Unpin + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(buffer);
+        tokio::spawn(async move { forward(self, &sender).await });
+        receiver
+    }
+}
+
+impl<T> TextStreamExt for T where T: Stream<Item = Result<String>> {}
+
+/// Sends every item of the stream to the channel, including the error.
+///
+/// Stops early once the receiver is dropped.
+pub async fn forward<S>(mut stream: S, sender: &mpsc::Sender<Result<String>>)
+where
+    S: Stream<Item = Result<String>> + Unpin,
+{
+    while let Some(item) = stream.next().await {
+        let error = item.is_err();
+        if sender.send(item).await.is_err() || error {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::{stream, AsyncReadExt, StreamExt};
+
+    use crate::lang::delta::{Boundary, TextStreamExt};
+
+    fn deltas(x: &[&str]) -> impl futures::Stream<Item = crate::Result<String>> + Unpin {
+        stream::iter(x.iter().map(|x| Ok((*x).to_owned())).collect::<Vec<_>>())
+    }
+
+    async fn rechunk(x: &[&str], boundary: Boundary) -> Vec<String> {
+        let stream = deltas(x).rechunk(boundary);
+        stream.map(Result::unwrap).collect().await
+    }
+
+    #[tokio::test]
+    async fn boundaries() {
+        let x = ["He", "llo wo", "rld.  Pi is 3.", "14! Ok\nBye"];
+        assert_eq!(
+            rechunk(&x, Boundary::Word).await,
+            ["Hello ", "world. ", " Pi ", "is ", "3.14! ", "Ok\n", "Bye"]
+        );
+        assert_eq!(
+            rechunk(&x, Boundary::Line).await,
+            ["Hello world.  Pi is 3.14! Ok\n", "Bye"]
+        );
+        assert_eq!(
+            rechunk(&x, Boundary::Sentence).await,
+            ["Hello world. ", " Pi is 3.14! ", "Ok\n", "Bye"]
+        );
+    }
+
+    #[tokio::test]
+    async fn read() {
+        let mut reader = deltas(&["Hel", "", "lo!"]).into_async_read();
+        let mut text = String::new();
+        reader.read_to_string(&mut text).await.unwrap();
+        assert_eq!(text, "Hello!");
+
+        let mut receiver = deltas(&["a", "b"]).into_channel(1);
+        assert_eq!(receiver.recv().await.unwrap().unwrap(), "a");
+        assert_eq!(receiver.recv().await.unwrap().unwrap(), "b");
+        assert!(receiver.recv().await.is_none());
+    }
+}
@@ -0,0 +1,283 @@
+//! Structural differences between two [`RouterConfigs`], e.g. of two gateways
+//! or of a gateway and its earlier snapshot.
+//!
+//! Routers and models are matched by their ids, not by their positions.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::lang::list::{RouterConfig, RouterConfigs};
+
+/// Kind of the [`ConfigChange`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// Single difference between two [`RouterConfigs`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigChange {
+    pub kind: ChangeKind,
+    /// Id of the affected router.
+    pub router: String,
+    /// Id of the affected model, if any.
+    pub model: Option<String>,
+    /// `JSON` pointer to the value, into the new configuration
+    /// or into the old one for [`ChangeKind::Removed`].
+    pub path: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let null = Value::Null;
+        let before = self.before.as_ref().unwrap_or(&null);
+        let after = self.after.as_ref().unwrap_or(&null);
+        match self.kind {
+            ChangeKind::Added => write!(f, "+ {}: {after}", self.path),
+            ChangeKind::Removed => write!(f, "- {}: {before}", self.path),
+            ChangeKind::Changed => write!(f, "~ {}: {before} -> {after}", self.path),
+        }
+    }
+}
+
+/// Returns all [`ConfigChange`]s from the `before` to the `after` configuration.
+pub(crate) fn diff(before: &RouterConfigs, after: &RouterConfigs) -> Vec<ConfigChange> {
+    let mut changes = Vec::new();
+
+    for (i, router) in before.routers.iter().enumerate() {
+        if after.get(&router.id).is_none() {
+            let path = format!("/routers/{i}");
+            let change = Change::new(&router.id, None, path.clone(), path);
+            changes.push(change.removed(to_value(router)));
+        }
+    }
+
+    for (i, router) in after.routers.iter().enumerate() {
+        let path = format!("/routers/{i}");
+        let position = before.routers.iter().position(|x| x.id == router.id);
+        match position {
+            Some(j) => {
+                let change = Change::new(&router.id, None, format!("/routers/{j}"), path);
+                diff_router(&mut changes, &change, &before.routers[j], router);
+            }
+            None => {
+                let change = Change::new(&router.id, None, path.clone(), path);
+                changes.push(change.added(to_value(router)));
+            }
+        }
+    }
+
+    changes
+}
+
+fn diff_router(
+    changes: &mut Vec<ConfigChange>,
+    change: &Change<'_>,
+    before: &RouterConfig,
+    after: &RouterConfig,
+) {
+    let strip = |x: &RouterConfig| {
+        let mut value = to_value(x);
+        if let Value::Object(x) = &mut value {
+            x.remove("models");
+        }
+
+        value
+    };
+
+    diff_value(changes, change, &strip(before), &strip(after));
+
+    for (i, model) in before.models.iter().enumerate() {
+        if after.model(&model.id).is_none() {
+            let path = format!("{}/models/{i}", change.old_path);
+            let change = Change::new(change.router, Some(&model.id), path.clone(), path);
+            changes.push(change.removed(to_value(model)));
+        }
+    }
+
+    for (i, model) in after.models.iter().enumerate() {
+        let path = format!("{}/models/{i}", change.path);
+        match before.models.iter().position(|x| x.id == model.id) {
+            Some(j) => {
+                let old_path = format!("{}/models/{j}", change.old_path);
+                let change = Change::new(change.router, Some(&model.id), old_path, path);
+                diff_value(
+                    changes,
+                    &change,
+                    &to_value(&before.models[j]),
+                    &to_value(model),
+                );
+            }
+            None => {
+                let change = Change::new(change.router, Some(&model.id), path.clone(), path);
+                changes.push(change.added(to_value(model)));
+            }
+        }
+    }
+}
+
+/// Pushes leaf differences of two values, arrays of different lengths are compared as a whole.
+fn diff_value(changes: &mut Vec<ConfigChange>, change: &Change, before: &Value, after: &Value) {
+    match (before, after) {
+        (Value::Object(x), Value::Object(y)) => {
+            let keys: BTreeSet<_> = x.keys().chain(y.keys()).collect();
+            for key in keys {
+                let change = change.child(key);
+                match (x.get(key), y.get(key)) {
+                    (Some(x), Some(y)) => diff_value(changes, &change, x, y),
+                    (Some(x), None) => changes.push(change.removed(x.clone())),
+                    (None, Some(y)) => changes.push(change.added(y.clone())),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Array(x), Value::Array(y)) if x.len() == y.len() => {
+            for (i, (x, y)) in x.iter().zip(y).enumerate() {
+                diff_value(changes, &change.child(&i.to_string()), x, y);
+            }
+        }
+        (x, y) if x != y => changes.push(change.changed(x.clone(), y.clone())),
+        _ => {}
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).expect("should be a valid `JSON`")
+}
+
+/// Location of the [`ConfigChange`].
+struct Change<'a> {
+    router: &'a str,
+    model: Option<&'a str>,
+    /// Pointer into the old configuration.
+    old_path: String,
+    /// Pointer into the new configuration.
+    path: String,
+}
+
+impl<'a> Change<'a> {
+    fn new(router: &'a str, model: Option<&'a str>, old_path: String, path: String) -> Self {
+        Self {
+            router,
+            model,
+            old_path,
+            path,
+        }
+    }
+
+    fn child(&self, key: &str) -> Self {
+        let key = key.replace('~', "~0").replace('/', "~1");
+        let old_path = format!("{}/{key}", self.old_path);
+        let path = format!("{}/{key}", self.path);
+        Self::new(self.router, self.model, old_path, path)
+    }
+
+    fn build(&self, kind: ChangeKind, before: Option<Value>, after: Option<Value>) -> ConfigChange {
+        let path = match kind {
+            ChangeKind::Removed => &self.old_path,
+            ChangeKind::Added | ChangeKind::Changed => &self.path,
+        };
+
+        ConfigChange {
+            kind,
+            router: self.router.to_owned(),
+            model: self.model.map(ToOwned::to_owned),
+            path: path.clone(),
+            before,
+            after,
+        }
+    }
+
+    fn added(&self, after: Value) -> ConfigChange {
+        self.build(ChangeKind::Added, None, Some(after))
+    }
+
+    fn removed(&self, before: Value) -> ConfigChange {
+        self.build(ChangeKind::Removed, Some(before), None)
+    }
+
+    fn changed(&self, before: Value, after: Value) -> ConfigChange {
+        self.build(ChangeKind::Changed, Some(before), Some(after))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::fixtures::TempDir;
+    use crate::lang::diff::ChangeKind;
+    use crate::lang::list::RouterConfigs;
+
+    #[test]
+    fn diff() {
+        let before: RouterConfigs = serde_json::from_value(json!({
+            "routers": [
+                { "routerID": "a", "strategy": "priority", "retry": { "max_retries": 3 }, "models": [
+                    { "id": "m", "enabled": true, "weight": 1, "openai": { "model": "gpt-4" } },
+                    { "id": "n", "enabled": true },
+                ] },
+                { "routerID": "b" },
+            ],
+        }))
+        .unwrap();
+
+        let after: RouterConfigs = serde_json::from_value(json!({
+            "routers": [
+                { "routerID": "c" },
+                { "routerID": "a", "strategy": "least_latency", "models": [
+                    { "id": "m", "enabled": true, "weight": 2, "openai": { "model": "gpt-4o" } },
+                    { "id": "o", "enabled": true },
+                ] },
+            ],
+        }))
+        .unwrap();
+
+        let changes = before.diff(&after);
+        let changes: Vec<_> = changes
+            .iter()
+            .map(|x| (x.kind, x.model.as_deref(), x.path.as_str()))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (ChangeKind::Removed, None, "/routers/1"),
+                (ChangeKind::Added, None, "/routers/0"),
+                (ChangeKind::Removed, None, "/routers/0/retry"),
+                (ChangeKind::Changed, None, "/routers/1/strategy"),
+                (ChangeKind::Removed, Some("n"), "/routers/0/models/1"),
+                (
+                    ChangeKind::Changed,
+                    Some("m"),
+                    "/routers/1/models/0/openai/model"
+                ),
+                (ChangeKind::Changed, Some("m"), "/routers/1/models/0/weight"),
+                (ChangeKind::Added, Some("o"), "/routers/1/models/1"),
+            ]
+        );
+
+        assert!(after.diff(&after).is_empty());
+    }
+
+    #[test]
+    fn snapshot() {
+        let configs: RouterConfigs = serde_json::from_value(json!({
+            "routers": [{ "routerID": "a", "models": [{ "id": "m", "weight": 1 }] }],
+        }))
+        .unwrap();
+
+        let dir = TempDir::new("routers");
+        let path = dir.path().join("routers.json");
+        configs.save(&path).unwrap();
+        let snapshot = RouterConfigs::from_file(&path).unwrap();
+        assert!(snapshot.diff(&configs).is_empty());
+    }
+}
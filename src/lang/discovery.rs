@@ -0,0 +1,420 @@
+//! Cached view of [`Language::list`] for pre-flight `router` name validation.
+//!
+//! [`Language::list`]: crate::lang::Language::list
+
+#[cfg(feature = "runtime")]
+use std::sync::{Arc, Mutex, PoisonError};
+#[cfg(feature = "runtime")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "runtime")]
+use tokio::sync::broadcast;
+#[cfg(feature = "runtime")]
+use tokio::task::JoinHandle;
+
+#[cfg(feature = "runtime")]
+use crate::lang::diff::ChangeKind;
+use crate::lang::list::RouterConfigs;
+#[cfg(feature = "runtime")]
+use crate::lang::Language;
+#[cfg(feature = "runtime")]
+use crate::Result;
+
+/// Capacity of the [`RouterEvent`] channel.
+#[cfg(feature = "runtime")]
+const EVENT_CAPACITY: usize = 16;
+/// Minimum age of the list refreshed to recheck an unknown `router`.
+#[cfg(feature = "runtime")]
+const MIN_REFRESH_AGE: Duration = Duration::from_secs(1);
+/// Maximum number of suggested `router` names.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Change of the `router` list between two refreshes.
+#[cfg(feature = "runtime")]
+#[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouterEvent {
+    /// The `router` appeared.
+    Added(String),
+    /// The `router` disappeared.
+    Removed(String),
+    /// The configuration of the `router` changed.
+    Changed(String),
+}
+
+/// Request to a `router` that is not in the `RouterCache`.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("unknown router `{router}`{}", DidYouMean(suggestions))]
+pub struct UnknownRouter {
+    pub router: String,
+    /// Known `router`s with similar names, the most similar first.
+    pub suggestions: Vec<String>,
+}
+
+impl UnknownRouter {
+    /// Creates a new [`UnknownRouter`] with suggestions from the known `router`s.
+    pub(crate) fn new(router: &str, configs: &RouterConfigs) -> Self {
+        let names = configs.routers.iter().map(|x| x.id.as_str());
+        Self {
+            router: router.to_owned(),
+            suggestions: suggest(router, names),
+        }
+    }
+}
+
+struct DidYouMean<'a>(&'a [String]);
+
+impl std::fmt::Display for DidYouMean<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<_> = self.0.iter().map(|x| format!("`{x}`")).collect();
+        match names.as_slice() {
+            [] => Ok(()),
+            [name] => write!(f, ", did you mean {name}?"),
+            [names @ .., name] => write!(f, ", did you mean {} or {name}?", names.join(", ")),
+        }
+    }
+}
+
+#[cfg(feature = "runtime")]
+#[derive(Debug, Default)]
+struct State {
+    configs: Option<Arc<RouterConfigs>>,
+    fetched: Option<Instant>,
+}
+
+#[cfg(feature = "runtime")]
+#[derive(Debug)]
+struct Inner {
+    ttl: Duration,
+    state: Mutex<State>,
+    refresh: tokio::sync::Mutex<()>,
+    events: broadcast::Sender<RouterEvent>,
+}
+
+/// Cached `router` list, refreshed when older than its time-to-live.
+///
+/// Once attached with [`Builder::with_router_cache`], [`Language::chat`]
+/// and [`Language::stream`] fail fast with [`Error::UnknownRouter`]
+/// instead of sending requests to unknown `router`s.
+///
+/// Cheap to clone, all clones share the same list.
+///
+/// [`Builder::with_router_cache`]: crate::Builder::with_router_cache
+/// [`Language::chat`]: crate::lang::Language::chat
+/// [`Language::stream`]: crate::lang::Language::stream
+/// [`Error::UnknownRouter`]: crate::Error::UnknownRouter
+#[cfg(feature = "runtime")]
+#[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+#[must_use]
+#[derive(Debug, Clone)]
+pub struct RouterCache(Arc<Inner>);
+
+#[cfg(feature = "runtime")]
+impl RouterCache {
+    /// Creates a new empty [`RouterCache`] with the specified time-to-live.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the `ttl` is zero.
+    pub fn new(ttl: Duration) -> Self {
+        assert!(!ttl.is_zero(), "`ttl` must be non-zero");
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        Self(Arc::new(Inner {
+            ttl,
+            state: Mutex::default(),
+            refresh: tokio::sync::Mutex::new(()),
+            events,
+        }))
+    }
+
+    /// Returns the time-to-live of the list.
+    #[inline]
+    #[must_use]
+    pub fn ttl(&self) -> Duration {
+        self.0.ttl
+    }
+
+    /// Returns the cached list without refreshing it, if any.
+    #[must_use]
+    pub fn snapshot(&self) -> Option<Arc<RouterConfigs>> {
+        self.lock().configs.clone()
+    }
+
+    /// Returns a new receiver of [`RouterEvent`]s emitted on every refresh.
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<RouterEvent> {
+        self.0.events.subscribe()
+    }
+
+    /// Returns the cached list, refreshing it first if it is stale.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the list is stale and cannot be refreshed.
+    ///
+    /// [`Error`]: crate::Error
+    pub async fn get(&self, lang: &Language) -> Result<Arc<RouterConfigs>> {
+        if let Some(configs) = self.fresh() {
+            return Ok(configs);
+        }
+
+        let _guard = self.0.refresh.lock().await;
+        match self.fresh() {
+            Some(configs) => Ok(configs),
+            None => self.update(lang).await,
+        }
+    }
+
+    /// Refreshes the cached list regardless of its age.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the response [`StatusCode`] is not in the 200-299 range.
+    ///
+    /// [`Error`]: crate::Error
+    /// [`StatusCode`]: reqwest::StatusCode
+    pub async fn refresh(&self, lang: &Language) -> Result<Arc<RouterConfigs>> {
+        let _guard = self.0.refresh.lock().await;
+        self.update(lang).await
+    }
+
+    /// Spawns the task refreshing the list every time-to-live.
+    ///
+    /// Failed refreshes are retried on the next tick. The task runs
+    /// until aborted with the returned [`JoinHandle`].
+    ///
+    /// ### Panics
+    ///
+    /// Panics if called outside of the `tokio` runtime.
+    pub fn spawn_refresh(&self, lang: Language) -> JoinHandle<()> {
+        let cache = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(cache.ttl());
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let _ = cache.refresh(&lang).await;
+            }
+        })
+    }
+
+    /// Checks that the `router` is in the list, refreshing it first if it is stale.
+    ///
+    /// Unknown `router`s are checked once more against the refreshed list,
+    /// as they may have been added since, unless the list was fetched
+    /// less than a second ago. Passes if the list cannot be refreshed,
+    /// leaving the check to the gateway.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the `router` is unknown.
+    ///
+    /// [`Error`]: crate::Error
+    pub async fn validate(&self, lang: &Language, router: &str) -> Result<()> {
+        let Ok(configs) = self.get(lang).await else {
+            return Ok(());
+        };
+
+        if configs.get(router).is_some() {
+            return Ok(());
+        }
+
+        let _guard = self.0.refresh.lock().await;
+        let recent = {
+            let state = self.lock();
+            let fetched = state.fetched.filter(|x| x.elapsed() < MIN_REFRESH_AGE);
+            fetched.and(state.configs.clone())
+        };
+
+        let configs = match recent {
+            Some(configs) => configs,
+            None => match self.update(lang).await {
+                Ok(configs) => configs,
+                Err(_) => return Ok(()),
+            },
+        };
+
+        match configs.get(router) {
+            Some(_) => Ok(()),
+            None => Err(UnknownRouter::new(router, &configs).into()),
+        }
+    }
+
+    fn fresh(&self) -> Option<Arc<RouterConfigs>> {
+        let state = self.lock();
+        let fetched = state.fetched?;
+        (fetched.elapsed() < self.0.ttl)
+            .then(|| state.configs.clone())
+            .flatten()
+    }
+
+    async fn update(&self, lang: &Language) -> Result<Arc<RouterConfigs>> {
+        let configs = Arc::new(lang.list().await?);
+        let previous = {
+            let mut state = self.lock();
+            state.fetched = Some(Instant::now());
+            state.configs.replace(configs.clone())
+        };
+
+        for event in diff(previous.as_deref(), &configs) {
+            let _ = self.0.events.send(event);
+        }
+
+        Ok(configs)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.0.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Returns [`RouterEvent`]s between the `previous` and the `current` list.
+#[cfg(feature = "runtime")]
+fn diff(previous: Option<&RouterConfigs>, current: &RouterConfigs) -> Vec<RouterEvent> {
+    let Some(previous) = previous else {
+        let ids = current.routers.iter().map(|x| x.id.clone());
+        return ids.map(RouterEvent::Added).collect();
+    };
+
+    // Changes of the same router are adjacent, so are merged into a single event.
+    let mut events = Vec::new();
+    for change in previous.diff(current) {
+        let event = match (change.kind, change.model) {
+            (ChangeKind::Added, None) => RouterEvent::Added(change.router),
+            (ChangeKind::Removed, None) => RouterEvent::Removed(change.router),
+            _ => RouterEvent::Changed(change.router),
+        };
+
+        if events.last() != Some(&event) {
+            events.push(event);
+        }
+    }
+
+    events
+}
+
+/// Returns the known names closest to the `name`, the most similar first.
+fn suggest<'a>(name: &str, known: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let threshold = (name.chars().count() / 3).max(2);
+    let mut candidates: Vec<_> = known
+        .map(|x| (levenshtein(name, x), x))
+        .filter(|(distance, _)| *distance <= threshold)
+        .collect();
+
+    candidates.sort_unstable();
+    let candidates = candidates.into_iter().take(MAX_SUGGESTIONS);
+    candidates.map(|(_, x)| x.to_owned()).collect()
+}
+
+/// Returns the edit distance between two strings.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<_> = b.chars().collect();
+    let mut row: Vec<_> = (0..=b.len()).collect();
+
+    for (i, x) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(x != *y);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "runtime")]
+    use std::sync::atomic::Ordering;
+    #[cfg(feature = "runtime")]
+    use std::time::Duration;
+
+    #[cfg(feature = "runtime")]
+    use serde_json::json;
+
+    #[cfg(feature = "runtime")]
+    use crate::fixtures::serve;
+    #[cfg(feature = "runtime")]
+    use crate::lang::discovery::{diff, RouterCache, RouterEvent};
+    use crate::lang::discovery::{levenshtein, suggest, UnknownRouter};
+    #[cfg(feature = "runtime")]
+    use crate::lang::list::RouterConfigs;
+
+    #[cfg(feature = "runtime")]
+    fn configs(value: serde_json::Value) -> RouterConfigs {
+        serde_json::from_value(json!({ "routers": value })).unwrap()
+    }
+
+    #[test]
+    fn suggestions() {
+        assert_eq!(levenshtein("myrouter", "myrouter"), 0);
+        assert_eq!(levenshtein("myruoter", "myrouter"), 2);
+        assert_eq!(levenshtein("", "abc"), 3);
+
+        let known = ["myrouter", "myrouter2", "other"];
+        let suggestions = suggest("myroter", known.into_iter());
+        assert_eq!(suggestions, ["myrouter", "myrouter2"]);
+        assert!(suggest("unrelated", known.into_iter()).is_empty());
+
+        let error = UnknownRouter {
+            router: "myroter".to_owned(),
+            suggestions,
+        };
+
+        let message = "unknown router `myroter`, did you mean `myrouter` or `myrouter2`?";
+        assert_eq!(error.to_string(), message);
+    }
+
+    #[test]
+    #[cfg(feature = "runtime")]
+    fn events() {
+        let previous = configs(json!([{ "id": "a" }, { "id": "b" }]));
+        let current = configs(json!([{ "id": "b", "enabled": false }, { "id": "c" }]));
+
+        let events = diff(Some(&previous), &current);
+        assert_eq!(
+            events,
+            [
+                RouterEvent::Removed("a".to_owned()),
+                RouterEvent::Changed("b".to_owned()),
+                RouterEvent::Added("c".to_owned()),
+            ]
+        );
+
+        assert_eq!(diff(None, &previous).len(), 2);
+
+        let models = configs(json!([{ "id": "a", "models": [{ "id": "m" }, { "id": "n" }] }]));
+        let events = diff(Some(&previous), &models);
+        assert_eq!(
+            events,
+            [
+                RouterEvent::Removed("b".to_owned()),
+                RouterEvent::Changed("a".to_owned()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "runtime")]
+    async fn unknown() {
+        let (glide, requests) = serve(r#"{"routers": [{"id": "myrouter"}]}"#);
+        let cache = RouterCache::new(Duration::from_secs(60));
+
+        for _ in 0..3 {
+            let error = cache.validate(&glide.lang, "myroter").await;
+            assert!(matches!(error, Err(crate::Error::UnknownRouter(_))));
+        }
+
+        assert!(cache.validate(&glide.lang, "myrouter").await.is_ok());
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "runtime")]
+    #[should_panic(expected = "`ttl` must be non-zero")]
+    fn zero_ttl() {
+        let _ = RouterCache::new(Duration::ZERO);
+    }
+}
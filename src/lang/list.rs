@@ -1,15 +1,437 @@
 //! Request and response types for `/v1/language/list` endpoints.
 //!
+//! Unrecognized fields are kept in the `extra` maps, so configurations of
+//! newer gateway versions still deserialize and round-trip losslessly.
 
-use serde::Deserialize;
-use serde_json::Value;
+use std::path::Path;
+use std::time::Duration;
+use std::{fs, io};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use crate::lang::diff::{self, ConfigChange};
+use crate::lang::params::{
+    AnthropicParams, BedrockParams, CohereParams, OctoMlParams, OllamaParams, OpenAiParams,
+};
+use crate::lang::validate::{Diagnostic, Validator};
+use crate::Result;
 
 /// All router configurations.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RouterConfigs {
     /// List of all available routers.
-    pub routers: Vec<Value>,
+    pub routers: Vec<RouterConfig>,
+}
+
+impl RouterConfigs {
+    /// Loads the [`RouterConfigs`] from the `JSON` file in the format of [`Language::list`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the file cannot be read or is not a valid configuration.
+    ///
+    /// [`Language::list`]: crate::lang::Language::list
+    /// [`Error`]: crate::Error
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let configs = serde_json::from_str(&content)
+            .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x))?;
+        Ok(configs)
+    }
+
+    /// Saves the [`RouterConfigs`] to the `JSON` file, e.g. as a snapshot for
+    /// [`RouterConfigs::diff`] or [`Validator`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the file cannot be written.
+    ///
+    /// [`Error`]: crate::Error
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let content = serde_json::to_string_pretty(self).expect("should be a valid `JSON`");
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Returns the [`RouterConfig`] of the specified `router`.
+    #[must_use]
+    pub fn get(&self, router: &str) -> Option<&RouterConfig> {
+        self.routers.iter().find(|x| x.id == router)
+    }
+
+    /// Returns all [`Diagnostic`]s of the [`RouterConfigs`].
+    ///
+    /// Same as [`Validator::validate`] without checks of credentials.
+    #[must_use]
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        Validator::new().validate(self)
+    }
+
+    /// Returns all [`ConfigChange`]s from `self` to the `other` configuration.
+    #[must_use]
+    pub fn diff(&self, other: &Self) -> Vec<ConfigChange> {
+        diff::diff(self, other)
+    }
 }
 
 /// Single router configuration.
-pub type RouterConfig = Value;
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouterConfig {
+    #[serde(rename = "routerID", alias = "id")]
+    pub id: String,
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub strategy: RoutingStrategy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryConfig>,
+    #[serde(default)]
+    pub models: Vec<ModelConfig>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl RouterConfig {
+    /// Returns the [`ModelConfig`] of the specified `model`.
+    #[must_use]
+    pub fn model(&self, model: &str) -> Option<&ModelConfig> {
+        self.models.iter().find(|x| x.id == model)
+    }
+}
+
+/// Strategy of selecting the model of the router.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum RoutingStrategy {
+    /// The first healthy model in the list.
+    #[default]
+    Priority,
+    RoundRobin,
+    WeightedRoundRobin,
+    /// The healthy model with the lowest latency.
+    LeastLatency,
+    /// Strategy not recognized by this client.
+    Unknown(String),
+}
+
+impl RoutingStrategy {
+    /// Returns the name of the strategy, as used by the gateway.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Priority => "priority",
+            Self::RoundRobin => "round_robin",
+            Self::WeightedRoundRobin => "weighted_round_robin",
+            Self::LeastLatency => "least_latency",
+            Self::Unknown(x) => x.as_str(),
+        }
+    }
+}
+
+impl From<String> for RoutingStrategy {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "priority" => Self::Priority,
+            "round_robin" => Self::RoundRobin,
+            "weighted_round_robin" => Self::WeightedRoundRobin,
+            "least_latency" => Self::LeastLatency,
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<RoutingStrategy> for String {
+    fn from(value: RoutingStrategy) -> Self {
+        match value {
+            RoutingStrategy::Unknown(x) => x,
+            x => x.as_str().to_owned(),
+        }
+    }
+}
+
+/// Retry and backoff configuration of the router.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_multiplier: Option<u32>,
+    #[serde(default, with = "duration", skip_serializing_if = "Option::is_none")]
+    pub min_delay: Option<Duration>,
+    #[serde(default, with = "duration", skip_serializing_if = "Option::is_none")]
+    pub max_delay: Option<Duration>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Single model configuration of the router.
+///
+/// Exactly one provider is expected to be configured.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelConfig {
+    pub id: String,
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+    /// Relative weight for [`RoutingStrategy::WeightedRoundRobin`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<i32>,
+    /// Allowed error rate, e.g. `10/m`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_budget: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencyConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client: Option<ClientConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub openai: Option<ProviderConfig<OpenAiParams>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub azureopenai: Option<ProviderConfig<OpenAiParams>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anthropic: Option<ProviderConfig<AnthropicParams>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cohere: Option<ProviderConfig<CohereParams>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bedrock: Option<ProviderConfig<BedrockParams>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ollama: Option<ProviderConfig<OllamaParams>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub octoml: Option<ProviderConfig<OctoMlParams>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl ModelConfig {
+    /// Returns the ids of all configured providers, e.g. `openai`.
+    #[must_use]
+    pub fn providers(&self) -> Vec<&'static str> {
+        let providers = [
+            ("openai", self.openai.is_some()),
+            ("azureopenai", self.azureopenai.is_some()),
+            ("anthropic", self.anthropic.is_some()),
+            ("cohere", self.cohere.is_some()),
+            ("bedrock", self.bedrock.is_some()),
+            ("ollama", self.ollama.is_some()),
+            ("octoml", self.octoml.is_some()),
+        ];
+
+        let providers = providers.into_iter().filter(|(_, x)| *x);
+        providers.map(|(x, _)| x).collect()
+    }
+
+    /// Returns the name of the model of the first configured provider.
+    #[must_use]
+    pub fn model_name(&self) -> Option<&str> {
+        let names = [
+            self.openai.as_ref().map(|x| &x.model),
+            self.azureopenai.as_ref().map(|x| &x.model),
+            self.anthropic.as_ref().map(|x| &x.model),
+            self.cohere.as_ref().map(|x| &x.model),
+            self.bedrock.as_ref().map(|x| &x.model),
+            self.ollama.as_ref().map(|x| &x.model),
+            self.octoml.as_ref().map(|x| &x.model),
+        ];
+
+        names
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(String::as_str)
+            .next()
+    }
+}
+
+/// Latency tracking configuration of the model.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyConfig {
+    /// Weight of the latest sample of the moving average.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decay: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warmup_samples: Option<u32>,
+    #[serde(default, with = "duration", skip_serializing_if = "Option::is_none")]
+    pub update_interval: Option<Duration>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// `HTTP` client configuration of the model.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClientConfig {
+    #[serde(default, with = "duration", skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<Duration>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Provider configuration of the model with its default [`params`].
+///
+/// Default parameters that do not match `P` are kept untyped
+/// in the `extra` map under the `default_params` key.
+///
+/// [`params`]: crate::lang::params
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "ProviderRepr", bound(deserialize = "P: DeserializeOwned"))]
+pub struct ProviderConfig<P> {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chat_endpoint: Option<String>,
+    /// Name of the model, e.g. `gpt-4o`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_params: Option<P>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct ProviderRepr {
+    #[serde(default)]
+    base_url: Option<String>,
+    #[serde(default)]
+    chat_endpoint: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    default_params: Option<Value>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+impl<P: DeserializeOwned> From<ProviderRepr> for ProviderConfig<P> {
+    fn from(value: ProviderRepr) -> Self {
+        let mut extra = value.extra;
+        let default_params = value.default_params.and_then(|x| {
+            serde_json::from_value(x.clone())
+                .map_err(|_| extra.insert("default_params".to_owned(), x))
+                .ok()
+        });
+
+        Self {
+            base_url: value.base_url,
+            chat_endpoint: value.chat_endpoint,
+            model: value.model,
+            default_params,
+            extra,
+        }
+    }
+}
+
+/// Returns the default value of `enabled`, as of the gateway.
+const fn enabled() -> bool {
+    true
+}
+
+/// Durations are `JSON` integers of nanoseconds, as of `Go`'s `time.Duration`,
+/// or `Go`-style duration strings, e.g. `1.5s`.
+mod duration {
+    use super::{Deserialize, Deserializer, Duration, Serializer, Value};
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(x) => serializer.serialize_u64(u64::try_from(x.as_nanos()).unwrap_or(u64::MAX)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        use serde::de::Error;
+
+        match Option::<Value>::deserialize(deserializer)? {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::Number(x)) => x
+                .as_u64()
+                .map(|x| Some(Duration::from_nanos(x)))
+                .ok_or_else(|| D::Error::custom(format!("invalid duration: {x}"))),
+            Some(Value::String(x)) => crate::duration::parse(&x)
+                .map(Some)
+                .ok_or_else(|| D::Error::custom(format!("invalid duration: {x}"))),
+            Some(x) => Err(D::Error::custom(format!("invalid duration: {x}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::lang::list::{ProviderConfig, RouterConfigs, RoutingStrategy};
+    use crate::lang::params::CohereParams;
+
+    #[test]
+    fn deserialize() {
+        let value = json!({
+            "routers": [{
+                "routerID": "myrouter",
+                "strategy": "least_latency",
+                "retry": { "max_retries": 3, "base_multiplier": 2, "min_delay": 2_000_000_000_u64, "max_delay": "5s" },
+                "models": [{
+                    "id": "openai",
+                    "enabled": true,
+                    "weight": 1,
+                    "error_budget": "1000/s",
+                    "latency": { "decay": 0.06, "warmup_samples": 3, "update_interval": 30_000_000_000_u64 },
+                    "client": { "timeout": 10_000_000_000_u64 },
+                    "openai": {
+                        "base_url": "https://api.openai.com/v1",
+                        "chat_endpoint": "/chat/completions",
+                        "model": "gpt-4o",
+                        "default_params": { "temperature": 0.8, "stop": "END", "logprobs": true },
+                    },
+                    "sticky": true,
+                }],
+                "fallback": "none",
+            }],
+        });
+
+        let configs: RouterConfigs = serde_json::from_value(value).unwrap();
+        let router = configs.get("myrouter").unwrap();
+        assert!(router.enabled);
+        assert_eq!(router.strategy, RoutingStrategy::LeastLatency);
+        assert_eq!(router.extra["fallback"], "none");
+
+        let retry = router.retry.as_ref().unwrap();
+        assert_eq!(retry.min_delay, Some(Duration::from_secs(2)));
+        assert_eq!(retry.max_delay, Some(Duration::from_secs(5)));
+
+        let model = router.model("openai").unwrap();
+        assert_eq!(model.providers(), ["openai"]);
+        assert_eq!(model.model_name(), Some("gpt-4o"));
+        assert_eq!(model.extra["sticky"], true);
+
+        let params = model.openai.as_ref().unwrap().default_params.as_ref();
+        let params = params.unwrap();
+        assert_eq!(params.temperature, Some(0.8));
+        assert_eq!(params.stop, Some(vec!["END".to_owned()]));
+        assert_eq!(params.extra["logprobs"], true);
+
+        let value = json!({ "model": "command-r", "default_params": { "temperature": "hot" } });
+        let cohere: ProviderConfig<CohereParams> = serde_json::from_value(value.clone()).unwrap();
+        assert!(cohere.default_params.is_none());
+        assert_eq!(serde_json::to_value(&cohere).unwrap(), value);
+
+        let value = serde_json::to_value(&configs).unwrap();
+        assert_eq!(value["routers"][0]["retry"]["max_delay"], 5_000_000_000_u64);
+        let configs2: RouterConfigs = serde_json::from_value(value).unwrap();
+        assert_eq!(configs, configs2);
+    }
+
+    #[test]
+    fn unknown_strategy() {
+        let value = json!({ "routers": [{ "id": "a", "strategy": "random" }] });
+        let configs: RouterConfigs = serde_json::from_value(value).unwrap();
+        let strategy = &configs.routers[0].strategy;
+        assert_eq!(strategy, &RoutingStrategy::Unknown("random".to_owned()));
+        assert_eq!(strategy.as_str(), "random");
+    }
+}
@@ -1,22 +1,63 @@
 //! Language service, its request and response types.
 //!
 
+use std::collections::HashMap;
 use std::fmt;
 use std::sync::Arc;
+use std::time::Duration;
 
 use reqwest::Method;
 
 use crate::config::Config;
-use crate::lang::chat::{ChatRequest, ChatResponse};
+use crate::lang::chat::{ChatMessage, ChatRequest, ChatResponse};
+pub use crate::lang::conversation::Conversation;
 use crate::lang::list::RouterConfigs;
 #[cfg(feature = "streaming")]
 #[cfg_attr(docsrs, doc(cfg(feature = "streaming")))]
-pub use crate::lang::stream::Chat;
-use crate::Result;
+pub use crate::lang::multiplex::{MultiplexedStream, Multiplexer};
+#[cfg(feature = "streaming")]
+#[cfg_attr(docsrs, doc(cfg(feature = "streaming")))]
+pub use crate::lang::reconnect::{ReconnectingChat, ReconnectingEvent};
+pub use crate::lang::router::Router;
+#[cfg(feature = "streaming")]
+#[cfg_attr(docsrs, doc(cfg(feature = "streaming")))]
+pub use crate::lang::stream::{Chat, RawChat};
+#[cfg(feature = "streaming")]
+use crate::types::ErrorKind;
+use crate::types::{Capability, Timeout, TimeoutKind};
+use crate::{Error, Result};
 
+#[cfg(feature = "streaming")]
+#[cfg_attr(docsrs, doc(cfg(feature = "streaming")))]
+pub mod aggregate;
+pub mod budget;
 pub mod chat;
+#[cfg(feature = "streaming")]
+#[cfg_attr(docsrs, doc(cfg(feature = "streaming")))]
+pub mod chat_stream;
+pub mod content;
+pub mod cost;
+#[cfg(feature = "streaming")]
+#[cfg_attr(docsrs, doc(cfg(feature = "streaming")))]
+pub mod delta;
+pub mod diff;
+pub mod discovery;
 pub mod list;
+pub mod params;
+pub mod structured;
+pub mod template;
+pub mod tokenizer;
+pub mod tools;
+pub mod truncate;
+pub mod validate;
+
+mod conversation;
+mod router;
 
+#[cfg(feature = "streaming")]
+mod multiplex;
+#[cfg(feature = "streaming")]
+mod reconnect;
 #[cfg(feature = "streaming")]
 mod stream;
 
@@ -42,48 +83,295 @@ impl Language {
         Ok(content)
     }
 
+    /// Retrieves a list of all `router` configs and saves it to the `JSON` file.
+    ///
+    /// The snapshot can be loaded with [`RouterConfigs::from_file`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the response [`StatusCode`] is not in the 200-299 range
+    /// or the file cannot be written.
+    ///
+    /// [`Error`]: crate::Error
+    /// [`StatusCode`]: reqwest::StatusCode
+    pub async fn snapshot(&self, path: impl AsRef<std::path::Path>) -> Result<RouterConfigs> {
+        let configs = self.list().await?;
+        configs.save(path)?;
+        Ok(configs)
+    }
+
+    /// Creates a new [`Router`] handle of a specified `router`.
+    #[inline]
+    pub fn router(&self, router: &str) -> Router {
+        Router::new(self.clone(), router)
+    }
+
+    /// Applies the [`TruncationPolicy`] configured for a specified `router`
+    /// and returns the dropped messages.
+    ///
+    /// Applying the policy again to the truncated request drops nothing,
+    /// so the request may then be sent with [`Language::chat`].
+    ///
+    /// [`TruncationPolicy`]: truncate::TruncationPolicy
+    pub fn truncate(&self, router: &str, data: &mut ChatRequest) -> Vec<ChatMessage> {
+        let policy = self.0.truncation(router);
+        policy.map(|x| x.truncate(data)).unwrap_or_default()
+    }
+
+    /// Checks the `router` name against the attached [`RouterCache`], if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the `router` is unknown.
+    ///
+    /// [`RouterCache`]: discovery::RouterCache
+    /// [`Error`]: crate::Error
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+    pub async fn validate_router(&self, router: &str) -> Result<()> {
+        match &self.0.routers {
+            Some(cache) => cache.validate(self, router).await,
+            None => Ok(()),
+        }
+    }
+
     /// Sends a single chat request to a specified `router` and retrieves the response.
     ///
+    /// The message history is truncated before sending, call [`Language::truncate`]
+    /// beforehand to retrieve the dropped messages. The response is recorded by the attached [`CostTracker`], if any.
+    ///
+    /// The estimated cost is reserved in the attached [`Budgets`], if any,
+    /// and reconciled with the real token usage of the response.
+    ///
     /// `POST /v1/language/{router}/chat`
     ///
     /// # Errors
     ///
-    /// Returns an [`Error`] if the response [`StatusCode`] is not in the 200-299 range.
+    /// Returns an [`Error`] if the response [`StatusCode`] is not in the 200-299 range
+    /// or the request would exceed a budget or the `router` is unknown
+    /// to the attached `RouterCache`, or has override parameters the
+    /// gateway does not support, see [`Builder::with_gateway_version`].
     ///
+    /// [`Builder::with_gateway_version`]: crate::Builder::with_gateway_version
+    /// [`Budgets`]: budget::Budgets
+    /// [`CostTracker`]: cost::CostTracker
     /// [`Error`]: crate::Error
     /// [`StatusCode`]: reqwest::StatusCode
     pub async fn chat(&self, router: &str, data: ChatRequest) -> Result<ChatResponse> {
+        self.chat_with(router, data, None, None).await
+    }
+
+    /// Sends a single chat request, recorded and budgeted with the custom `tag`.
+    ///
+    /// The reservation is kept if the request times out, as the gateway may still charge it.
+    pub(crate) async fn chat_with(
+        &self,
+        router: &str,
+        mut data: ChatRequest,
+        tag: Option<&str>,
+        timeout: Option<Duration>,
+    ) -> Result<ChatResponse> {
+        let mut overrides = data.override_params.iter().flat_map(HashMap::values);
+        if overrides.any(|x| x.params.is_some()) {
+            self.0.require(Capability::Overrides)?;
+        }
+
+        #[cfg(feature = "runtime")]
+        self.validate_router(router).await?;
         let path = format!("/v1/language/{router}/chat");
+        self.truncate(router, &mut data);
+        let reservation = match &self.0.budgets {
+            Some(budgets) => Some(budgets.reserve(router, &data, tag)?),
+            None => None,
+        };
+
+        let mut request = self.0.create(Method::POST, &path).json(&data);
+        if let Some(timeout) = timeout {
+            request = request.timeout(timeout);
+        }
+
+        let response = async {
+            let response = self.0.send(request).await?;
+            Ok(response.json::<ChatResponse>().await?)
+        };
+
+        let content = match response.await {
+            Ok(x) => x,
+            Err(Error::Http(x)) if x.is_timeout() => {
+                if let Some(reservation) = reservation {
+                    reservation.keep();
+                }
+
+                return Err(match timeout {
+                    Some(after) => Timeout {
+                        kind: TimeoutKind::Request,
+                        after,
+                    }
+                    .into(),
+                    None => x.into(),
+                });
+            }
+            Err(x) => return Err(x),
+        };
 
-        let request = self.0.create(Method::POST, &path);
-        let response = self.0.send(request.json(&data)).await?;
-        let content = response.json::<ChatResponse>().await?;
+        if let Some(reservation) = reservation {
+            reservation.settle(&content);
+        }
+
+        if let Some(tracker) = &self.0.cost {
+            tracker.record(&content, tag);
+        }
 
         Ok(content)
     }
 
+    /// Sends a single chat request to a specified `router` and deserializes
+    /// the `JSON` value embedded in the response.
+    ///
+    /// Same as [`Language::chat_json_with_retries`] without retries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the response [`StatusCode`] is not in the 200-299 range
+    /// or the response does not match `T`.
+    ///
+    /// [`Error`]: crate::Error
+    /// [`StatusCode`]: reqwest::StatusCode
+    #[cfg(feature = "schema")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "schema")))]
+    pub async fn chat_json<T>(&self, router: &str, data: ChatRequest) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned + schemars::JsonSchema,
+    {
+        self.chat_json_with_retries(router, data, 0).await
+    }
+
+    /// Sends a single chat request to a specified `router` and deserializes
+    /// the `JSON` value embedded in the response.
+    ///
+    /// The request is extended with the `JSON` schema of `T`. If the response
+    /// does not match `T`, the parsing error is sent back up to `retries` times.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the response [`StatusCode`] is not in the 200-299 range
+    /// or the last response does not match `T`.
+    ///
+    /// [`Error`]: crate::Error
+    /// [`StatusCode`]: reqwest::StatusCode
+    #[cfg(feature = "schema")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "schema")))]
+    pub async fn chat_json_with_retries<T>(
+        &self,
+        router: &str,
+        data: ChatRequest,
+        retries: usize,
+    ) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned + schemars::JsonSchema,
+    {
+        use crate::lang::structured::{parse_json, with_correction, with_schema, StructuredError};
+
+        let schema = schemars::schema_for!(T);
+        let schema = serde_json::to_string(&schema).expect("should be a valid `JSON`");
+        let mut data = with_schema(data, &schema);
+
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let response = self.chat(router, data.clone()).await?;
+            let error = match parse_json::<T>(&response.content()) {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+
+            if attempts > retries {
+                return Err(StructuredError {
+                    content: response.content().into_owned(),
+                    attempts,
+                    source: error,
+                }
+                .into());
+            }
+
+            let reply = response.model_response.message;
+            data = with_correction(data, reply, &error);
+        }
+    }
+
     /// Establishes a `WebSocket` connection for streaming chat messages from a specified `router`.
     ///
     /// `GET /v1/language/{router}/chatStream`
     ///
     /// # Errors
     ///
-    /// Returns an [`Error`] if the response [`StatusCode`] is not in the 200-299 range.
+    /// Returns an [`Error`] if the response [`StatusCode`] is not in the 200-299 range
+    /// or the `router` is unknown, see [`Language::validate_router`].
     ///
+    /// Returns [`Error::Unsupported`] if the gateway has no streaming endpoint.
+    ///
+    /// [`Error::Unsupported`]: crate::Error::Unsupported
     /// [`Error`]: crate::Error
     /// [`StatusCode`]: reqwest::StatusCode
     #[cfg(feature = "streaming")]
     #[cfg_attr(docsrs, doc(cfg(feature = "streaming")))]
     pub async fn stream(&self, router: &str) -> Result<Chat> {
         use reqwest_websocket::RequestBuilderExt as _;
+        self.0.require(Capability::Streaming)?;
+        self.validate_router(router).await?;
         let path = format!("/v1/language/{router}/chatStream");
 
         let request = self.0.create(Method::GET, &path).upgrade();
         let response = request.send().await?;
+        let status = response.status();
+        if status.is_client_error() || status.is_server_error() {
+            let error = Config::error(response.into_inner()).await?;
+            return match error.kind() {
+                ErrorKind::RouteNotFound => Err(self.0.unsupported(Capability::Streaming)),
+                _ => Err(error.into()),
+            };
+        }
+
         let websocket = response.into_websocket().await?;
 
         Ok(Chat::new(websocket))
     }
+
+    /// Establishes a shared `WebSocket` connection for concurrent streaming chats.
+    ///
+    /// See [`Language::stream`] and [`Multiplexer`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the response [`StatusCode`] is not in the 200-299 range
+    /// or the `router` is unknown, see [`Language::validate_router`].
+    ///
+    /// [`Error`]: crate::Error
+    /// [`StatusCode`]: reqwest::StatusCode
+    #[cfg(feature = "streaming")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "streaming")))]
+    pub async fn multiplex(&self, router: &str) -> Result<Multiplexer> {
+        let chat = self.stream(router).await?;
+        Ok(Multiplexer::new(chat))
+    }
+
+    /// Establishes a `WebSocket` connection that is re-established when lost.
+    ///
+    /// See [`Language::stream`] and [`ReconnectingChat`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the response [`StatusCode`] is not in the 200-299 range
+    /// or the `router` is unknown, see [`Language::validate_router`].
+    ///
+    /// [`Error`]: crate::Error
+    /// [`StatusCode`]: reqwest::StatusCode
+    #[cfg(feature = "streaming")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "streaming")))]
+    pub async fn reconnecting(&self, router: &str) -> Result<ReconnectingChat> {
+        let chat = self.stream(router).await?;
+        Ok(ReconnectingChat::new(self.clone(), router, chat))
+    }
 }
 
 impl fmt::Debug for Language {
@@ -99,6 +387,7 @@ mod test {
     use crate::{Client, Result};
 
     #[tokio::test]
+    #[allow(clippy::len_zero)]
     async fn list() -> Result<()> {
         let glide = Client::default();
         let response = glide.lang.list().await?;
@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+use std::future::ready;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures::{stream, Sink, SinkExt, Stream, StreamExt};
+
+use crate::lang::chat_stream::{next_id, ChatStreamMessage, ChatStreamRequest};
+use crate::lang::Chat;
+use crate::{Error, Result};
+
+type Sender = UnboundedSender<Result<ChatStreamMessage>>;
+
+struct Pending {
+    request: ChatStreamRequest,
+    sender: Sender,
+}
+
+#[allow(clippy::large_enum_variant)]
+enum Command {
+    Send(Pending),
+    /// The [`MultiplexedStream`] of the request was dropped.
+    Cancel(String),
+}
+
+enum Event {
+    Command(Command),
+    /// All [`Multiplexer`] handles and [`MultiplexedStream`]s were dropped.
+    Dropped,
+    Message(Result<ChatStreamMessage>),
+    /// The connection was closed.
+    Closed,
+}
+
+/// Shared streaming chat connection, created with [`Language::multiplex`].
+///
+/// Assigns ids to requests and routes every [`ChatStreamMessage`] to the
+/// [`MultiplexedStream`] of its request, so that many tasks may chat
+/// concurrently over a single `WebSocket` connection.
+///
+/// Cheap to clone, the connection is closed once all handles and
+/// streams are dropped.
+///
+/// #### Example
+///
+/// ```rust,no_run
+/// use futures::StreamExt;
+/// use glide_rs::Client;
+///
+/// # let _ = async {
+/// let glide = Client::default();
+/// let chat = glide.lang.multiplex("myrouter").await?;
+///
+/// let mut a = chat.send("Hello!");
+/// let mut b = chat.send("Bonjour !");
+/// while let Some(message) = a.next().await {
+///     print!("{}", message?.content());
+/// }
+/// # Ok::<_, glide_rs::Error>(())
+/// # };
+/// ```
+///
+/// [`Language::multiplex`]: crate::lang::Language::multiplex
+#[must_use]
+#[derive(Debug, Clone)]
+pub struct Multiplexer {
+    requests: UnboundedSender<Command>,
+}
+
+impl Multiplexer {
+    /// Creates a new [`Multiplexer`] over the [`Chat`] connection.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if called outside of the `tokio` runtime.
+    pub fn new(chat: Chat) -> Self {
+        let (sink, stream) = chat.split();
+        Self::spawn(sink, stream)
+    }
+
+    fn spawn<S, T>(sink: S, stream: T) -> Self
+    where
+        S: Sink<ChatStreamRequest, Error = Error> + Send + Unpin + 'static,
+        T: Stream<Item = Result<ChatStreamMessage>> + Send + Unpin + 'static,
+    {
+        let (requests, receiver) = unbounded();
+        tokio::spawn(drive(sink, stream, receiver));
+        Self { requests }
+    }
+
+    /// Sends the request and returns the [`MultiplexedStream`] of its response.
+    ///
+    /// Replaces the id of the request with a new one, unique within the connection.
+    /// Errors, including a closed connection, are yielded by the stream.
+    pub fn send(&self, data: impl Into<ChatStreamRequest>) -> MultiplexedStream {
+        let mut request = data.into();
+        request.id = next_id();
+
+        let id = request.id.clone();
+        let (sender, receiver) = unbounded();
+        let command = Command::Send(Pending { request, sender });
+        if let Err(x) = self.requests.unbounded_send(command) {
+            if let Command::Send(pending) = x.into_inner() {
+                let _ = pending
+                    .sender
+                    .unbounded_send(Err(aborted("connection is closed")));
+            }
+        }
+
+        MultiplexedStream {
+            id,
+            receiver,
+            requests: self.requests.clone(),
+        }
+    }
+
+    /// Returns `true` if the connection is closed.
+    #[inline]
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.requests.is_closed()
+    }
+}
+
+/// Stream of [`ChatStreamMessage`]s of a single request, created with [`Multiplexer::send`].
+///
+/// Ends after the final message of the response or the first error.
+/// Messages of the request are discarded once the stream is dropped.
+///
+/// If the connection times out, [`Error::Timeout`] is yielded as is. Other connection
+/// errors are yielded as [`io::ErrorKind::ConnectionAborted`] errors wrapping the
+/// shared original [`Error`], as all pending requests receive it.
+#[must_use = "streams do nothing unless you poll them"]
+#[derive(Debug)]
+pub struct MultiplexedStream {
+    id: String,
+    receiver: UnboundedReceiver<Result<ChatStreamMessage>>,
+    requests: UnboundedSender<Command>,
+}
+
+impl MultiplexedStream {
+    /// Returns the id assigned to the request.
+    #[inline]
+    #[must_use]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl Stream for MultiplexedStream {
+    type Item = Result<ChatStreamMessage>;
+
+    #[inline]
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_next_unpin(cx)
+    }
+}
+
+impl Drop for MultiplexedStream {
+    fn drop(&mut self) {
+        let id = std::mem::take(&mut self.id);
+        let _ = self.requests.unbounded_send(Command::Cancel(id));
+    }
+}
+
+/// Forwards requests to the connection and routes messages back by their ids.
+async fn drive<S, T>(mut sink: S, stream: T, mut requests: UnboundedReceiver<Command>)
+where
+    S: Sink<ChatStreamRequest, Error = Error> + Unpin,
+    T: Stream<Item = Result<ChatStreamMessage>> + Unpin,
+{
+    let mut pending: HashMap<String, Sender> = HashMap::new();
+    let mut last_error = None;
+
+    {
+        let incoming = (&mut requests)
+            .map(Event::Command)
+            .chain(stream::once(ready(Event::Dropped)));
+        let messages = stream
+            .map(Event::Message)
+            .chain(stream::once(ready(Event::Closed)));
+        let mut events = stream::select(incoming, messages);
+        let mut dropped = false;
+
+        while let Some(event) = events.next().await {
+            match event {
+                Event::Command(Command::Send(Pending { request, sender })) => {
+                    let id = request.id.clone();
+                    match sink.send(request).await {
+                        Ok(()) => drop(pending.insert(id, sender)),
+                        Err(x) => drop(sender.unbounded_send(Err(x))),
+                    }
+                }
+                Event::Command(Command::Cancel(id)) => drop(pending.remove(&id)),
+                Event::Dropped => dropped = true,
+                Event::Message(Ok(message)) => {
+                    let Some(sender) = pending.get(&message.id) else {
+                        continue;
+                    };
+
+                    let id = message.id.clone();
+                    let last = message.is_final();
+                    if sender.unbounded_send(Ok(message)).is_err() || last {
+                        pending.remove(&id);
+                    }
+                }
+                Event::Message(Err(Error::Decode(x))) => {
+                    // Messages without a known id can not be routed.
+                    let Some(sender) = x.id.as_ref().and_then(|x| pending.remove(x)) else {
+                        continue;
+                    };
+
+                    let _ = sender.unbounded_send(Err(x.into()));
+                }
+                Event::Message(Err(x)) => last_error = Some(Arc::new(x)),
+                Event::Closed => break,
+            }
+
+            if dropped && pending.is_empty() {
+                break;
+            }
+        }
+    }
+
+    requests.close();
+    while let Ok(x) = requests.try_recv() {
+        match x {
+            Command::Send(x) => drop(pending.insert(x.request.id, x.sender)),
+            Command::Cancel(id) => drop(pending.remove(&id)),
+        }
+    }
+
+    for (_, sender) in pending.drain() {
+        let _ = sender.unbounded_send(Err(closed(last_error.as_ref())));
+    }
+
+    let _ = sink.close().await;
+}
+
+/// Returns the error of the connection closed after the `error`, if any.
+fn closed(error: Option<&Arc<Error>>) -> Error {
+    match error {
+        Some(x) => match **x {
+            Error::Timeout(timeout) => Error::Timeout(timeout),
+            _ => aborted(x.clone()),
+        },
+        None => aborted("connection is closed"),
+    }
+}
+
+fn aborted(reason: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Error {
+    io::Error::new(io::ErrorKind::ConnectionAborted, reason).into()
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use futures::channel::mpsc::unbounded;
+    use futures::{SinkExt, StreamExt};
+
+    use crate::fixtures::message;
+    use crate::lang::chat_stream::{ChatStreamMessage, ChatStreamRequest, DecodeError};
+    use crate::lang::multiplex::Multiplexer;
+    use crate::types::{Timeout, TimeoutKind};
+    use crate::Error;
+
+    #[tokio::test]
+    async fn multiplex() {
+        let (sink, mut requests) = unbounded::<ChatStreamRequest>();
+        let (messages, stream) = unbounded();
+        let sink = sink.sink_map_err(|_| -> Error { unreachable!() });
+        let chat = Multiplexer::spawn(sink, stream);
+
+        let mut a = chat.send("Hello!");
+        let mut b = chat.send(ChatStreamRequest::new("Bonjour !").with_id("b"));
+        assert_ne!(b.id(), "b");
+        assert_eq!(requests.next().await.unwrap().id, a.id());
+        assert_eq!(requests.next().await.unwrap().id, b.id());
+
+        messages
+            .unbounded_send(Ok(message(b.id(), "Bon", false)))
+            .unwrap();
+        messages
+            .unbounded_send(Ok(message(a.id(), "Hi", true)))
+            .unwrap();
+        messages
+            .unbounded_send(Ok(message(b.id(), "jour", true)))
+            .unwrap();
+
+        assert_eq!(a.next().await.unwrap().unwrap().content(), "Hi");
+        assert!(a.next().await.is_none());
+        assert_eq!(b.next().await.unwrap().unwrap().content(), "Bon");
+        assert_eq!(b.next().await.unwrap().unwrap().content(), "jour");
+        assert!(b.next().await.is_none());
+
+        let mut c = chat.send("Hello?");
+        assert!(requests.next().await.is_some());
+        drop(messages);
+        assert!(matches!(c.next().await, Some(Err(Error::Io(_)))));
+        assert!(c.next().await.is_none());
+
+        let mut d = chat.send("Anyone?");
+        assert!(matches!(d.next().await, Some(Err(Error::Io(_)))));
+    }
+
+    #[tokio::test]
+    async fn timeout() {
+        let (sink, mut requests) = unbounded::<ChatStreamRequest>();
+        let (messages, stream) = unbounded();
+        let sink = sink.sink_map_err(|_| -> Error { unreachable!() });
+        let chat = Multiplexer::spawn(sink, stream);
+
+        let mut a = chat.send("Hello!");
+        let mut b = chat.send("Bonjour !");
+        assert!(requests.next().await.is_some());
+        assert!(requests.next().await.is_some());
+
+        let timeout = Timeout {
+            kind: TimeoutKind::Chunk,
+            after: Duration::from_secs(10),
+        };
+
+        messages.unbounded_send(Err(timeout.into())).unwrap();
+        drop(messages);
+
+        assert!(matches!(a.next().await, Some(Err(Error::Timeout(x))) if x == timeout));
+        assert!(matches!(b.next().await, Some(Err(Error::Timeout(x))) if x == timeout));
+    }
+
+    #[tokio::test]
+    async fn cancel() {
+        let (sink, mut requests) = unbounded::<ChatStreamRequest>();
+        let (messages, stream) = unbounded::<Result<ChatStreamMessage, Error>>();
+        let sink = sink.sink_map_err(|_| -> Error { unreachable!() });
+        let chat = Multiplexer::spawn(sink, stream);
+
+        let mut a = chat.send("Hello!");
+        let b = chat.send("Bonjour !");
+        assert!(requests.next().await.is_some());
+        assert!(requests.next().await.is_some());
+
+        let source = serde_json::from_str::<ChatStreamMessage>("{}").unwrap_err();
+        let id = Some(a.id().to_owned());
+        messages
+            .unbounded_send(Err(DecodeError { id, source }.into()))
+            .unwrap();
+        assert!(matches!(a.next().await, Some(Err(Error::Decode(_)))));
+        assert!(a.next().await.is_none());
+
+        // Nothing is pending after the streams are dropped, the connection is closed.
+        drop((a, b, chat));
+        assert!(requests.next().await.is_none());
+    }
+}
@@ -0,0 +1,266 @@
+//! Provider-specific model parameters for [`ChatRequestOverride`]s.
+//!
+//! Unrecognized parameters are kept in the `extra` maps, and sequences
+//! of stop words also accept a single string.
+//!
+//! [`ChatRequestOverride`]: crate::lang::chat::ChatRequestOverride
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Model parameters of a single provider.
+///
+/// Serialized under the provider key used by the gateway configuration,
+/// e.g. `{"openai": {"temperature": 0.2}}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ModelParams {
+    #[serde(rename = "openai")]
+    OpenAi(OpenAiParams),
+    #[serde(rename = "azureopenai")]
+    AzureOpenAi(OpenAiParams),
+    #[serde(rename = "anthropic")]
+    Anthropic(AnthropicParams),
+    #[serde(rename = "cohere")]
+    Cohere(CohereParams),
+    #[serde(rename = "bedrock")]
+    Bedrock(BedrockParams),
+    #[serde(rename = "ollama")]
+    Ollama(OllamaParams),
+    #[serde(rename = "octoml")]
+    OctoMl(OctoMlParams),
+    /// Raw `JSON` object for parameters not covered by the typed variants.
+    #[serde(untagged)]
+    Raw(Map<String, Value>),
+}
+
+impl From<OpenAiParams> for ModelParams {
+    #[inline]
+    fn from(value: OpenAiParams) -> Self {
+        Self::OpenAi(value)
+    }
+}
+
+impl From<AnthropicParams> for ModelParams {
+    #[inline]
+    fn from(value: AnthropicParams) -> Self {
+        Self::Anthropic(value)
+    }
+}
+
+impl From<CohereParams> for ModelParams {
+    #[inline]
+    fn from(value: CohereParams) -> Self {
+        Self::Cohere(value)
+    }
+}
+
+impl From<BedrockParams> for ModelParams {
+    #[inline]
+    fn from(value: BedrockParams) -> Self {
+        Self::Bedrock(value)
+    }
+}
+
+impl From<OllamaParams> for ModelParams {
+    #[inline]
+    fn from(value: OllamaParams) -> Self {
+        Self::Ollama(value)
+    }
+}
+
+impl From<OctoMlParams> for ModelParams {
+    #[inline]
+    fn from(value: OctoMlParams) -> Self {
+        Self::OctoMl(value)
+    }
+}
+
+impl From<Map<String, Value>> for ModelParams {
+    #[inline]
+    fn from(value: Map<String, Value>) -> Self {
+        Self::Raw(value)
+    }
+}
+
+/// `OpenAI` and `Azure OpenAI` chat parameters.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenAiParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<u32>,
+    #[serde(
+        default,
+        deserialize_with = "sequences::deserialize",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logit_bias: Option<HashMap<String, f64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// `Anthropic` chat parameters.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnthropicParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(
+        default,
+        deserialize_with = "sequences::deserialize",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub stop_sequences: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// `Cohere` chat parameters.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CohereParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preamble_override: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    #[serde(
+        default,
+        deserialize_with = "sequences::deserialize",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub stop_sequences: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// `AWS Bedrock` chat parameters.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BedrockParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(rename = "topP", skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(rename = "maxTokenCount", skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(
+        rename = "stopSequences",
+        default,
+        deserialize_with = "sequences::deserialize",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub stop_sequences: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// `Ollama` chat parameters.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OllamaParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_ctx: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_predict: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    #[serde(
+        default,
+        deserialize_with = "sequences::deserialize",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat_penalty: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mirostat: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mirostat_eta: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mirostat_tau: Option<f64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// `OctoML` chat parameters.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OctoMlParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(
+        default,
+        deserialize_with = "sequences::deserialize",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Sequences are `JSON` arrays of strings or a single string.
+mod sequences {
+    use serde::{Deserialize, Deserializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<String>>, D::Error> {
+        let value = Option::<Repr>::deserialize(deserializer)?;
+        Ok(value.map(|x| match x {
+            Repr::One(x) => vec![x],
+            Repr::Many(x) => x,
+        }))
+    }
+}
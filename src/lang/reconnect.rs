@@ -0,0 +1,471 @@
+use std::collections::{HashSet, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use tokio::time::Sleep;
+
+use crate::lang::chat_stream::{ChatStreamMessage, ChatStreamRequest};
+use crate::lang::stream::Timeouts;
+use crate::lang::{Chat, Language};
+use crate::{Error, Result};
+
+type Connect = Pin<Box<dyn Future<Output = Result<Chat>> + Send>>;
+
+/// Item of the [`ReconnectingChat`].
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReconnectingEvent {
+    /// Message of the current connection.
+    Message(ChatStreamMessage),
+    /// The connection was lost, with the reason if any.
+    Disconnected { reason: Option<String> },
+    /// The next attempt to reconnect starts after the delay.
+    Reconnecting { attempt: u32, delay: Duration },
+    /// The connection was re-established.
+    Reconnected {
+        attempt: u32,
+        /// Ids of incomplete requests sent again.
+        resent: Vec<String>,
+        /// Ids of incomplete requests abandoned, see [`ReconnectingChat::with_resend`].
+        dropped: Vec<String>,
+    },
+}
+
+enum State {
+    Connected(Box<Chat>),
+    Waiting(Pin<Box<Sleep>>),
+    Connecting(Connect),
+    Closed,
+}
+
+/// Streaming chat connection that re-establishes itself, created with [`Language::reconnecting`].
+///
+/// Implements `futures::`[`Stream`] of [`ReconnectingEvent`]s
+/// and `futures::`[`Sink`] of [`ChatStreamRequest`]s.
+///
+/// Requests are buffered while disconnected and sent once reconnected,
+/// the connection is only re-established while the stream is polled.
+///
+/// #### Example
+///
+/// ```rust,no_run
+/// use futures::{SinkExt, StreamExt};
+/// use glide_rs::lang::chat_stream::ChatStreamRequest;
+/// use glide_rs::lang::ReconnectingEvent;
+/// use glide_rs::Client;
+///
+/// # let _ = async {
+/// let glide = Client::default();
+/// let mut chat = glide.lang.reconnecting("myrouter").await?.with_max_attempts(5);
+/// chat.send(ChatStreamRequest::new("Hello!")).await?;
+///
+/// while let Some(event) = chat.next().await {
+///     match event? {
+///         ReconnectingEvent::Message(x) => print!("{}", x.content()),
+///         x => eprintln!("{x:?}"),
+///     }
+/// }
+/// # Ok::<_, glide_rs::Error>(())
+/// # };
+/// ```
+///
+/// [`Language::reconnecting`]: crate::lang::Language::reconnecting
+#[must_use = "streams do nothing unless you poll them"]
+pub struct ReconnectingChat {
+    lang: Language,
+    router: String,
+    state: State,
+    requests: Requests,
+    events: VecDeque<ReconnectingEvent>,
+    attempt: u32,
+    backoff: Backoff,
+    max_attempts: Option<u32>,
+    resend: bool,
+    timeouts: Timeouts,
+}
+
+impl ReconnectingChat {
+    /// Creates a new [`ReconnectingChat`] over the established connection.
+    pub(crate) fn new(lang: Language, router: &str, chat: Chat) -> Self {
+        Self {
+            lang,
+            router: router.to_owned(),
+            state: State::Connected(Box::new(chat)),
+            requests: Requests::default(),
+            events: VecDeque::new(),
+            attempt: 0,
+            backoff: Backoff::default(),
+            max_attempts: Some(10),
+            resend: true,
+            timeouts: Timeouts::default(),
+        }
+    }
+
+    /// Overrides the delay before the first and the longest delay before
+    /// any following attempt to reconnect, doubled after every attempt.
+    ///
+    /// Default value: `500ms` and `30s`
+    pub fn with_backoff(mut self, min: Duration, max: Duration) -> Self {
+        self.backoff = Backoff { min, max };
+        self
+    }
+
+    /// Overrides the number of consecutive attempts to reconnect, `None` for unlimited.
+    ///
+    /// With `Some(0)`, the stream ends once disconnected.
+    ///
+    /// Default value: `Some(10)`
+    pub fn with_max_attempts(mut self, attempts: impl Into<Option<u32>>) -> Self {
+        self.max_attempts = attempts.into();
+        self
+    }
+
+    /// Enables sending incomplete requests again once reconnected.
+    ///
+    /// Responses of resent requests start over, messages received
+    /// before the disconnect are not repeated.
+    /// Requests buffered while disconnected are sent either way.
+    ///
+    /// Default value: `true`
+    pub fn with_resend(mut self, enabled: bool) -> Self {
+        self.resend = enabled;
+        self
+    }
+
+    /// Sends a `ping` frame every `interval` over every connection.
+    ///
+    /// See [`Chat::with_keepalive`].
+    ///
+    /// Default value: `None`
+    pub fn with_keepalive(self, interval: Duration) -> Self {
+        self.configure(|x| x.keepalive = Some(interval))
+    }
+
+    /// Reconnects if no frames are received for the duration.
+    ///
+    /// See [`Chat::with_idle_timeout`].
+    ///
+    /// Default value: `None`
+    pub fn with_idle_timeout(self, timeout: Duration) -> Self {
+        self.configure(|x| x.idle = Some(timeout))
+    }
+
+    /// Reconnects if no messages are received for the duration
+    /// while any response is incomplete.
+    ///
+    /// See [`Chat::with_chunk_timeout`].
+    ///
+    /// Default value: `None`
+    pub fn with_chunk_timeout(self, timeout: Duration) -> Self {
+        self.configure(|x| x.chunk = Some(timeout))
+    }
+
+    fn configure(mut self, update: impl FnOnce(&mut Timeouts)) -> Self {
+        update(&mut self.timeouts);
+        self.state = match self.state {
+            State::Connected(chat) => State::Connected(Box::new(chat.with_timeouts(self.timeouts))),
+            x => x,
+        };
+
+        self
+    }
+
+    /// Returns `true` if the connection is established.
+    #[inline]
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        matches!(self.state, State::Connected(_))
+    }
+
+    /// Closes the underlying connection, if established.
+    pub async fn close(self) -> Result<()> {
+        match self.state {
+            State::Connected(chat) => Chat::close(*chat).await,
+            _ => Ok(()),
+        }
+    }
+
+    fn disconnect(&mut self, reason: Option<String>) {
+        self.events
+            .push_back(ReconnectingEvent::Disconnected { reason });
+        match self.max_attempts {
+            Some(0) => self.state = State::Closed,
+            _ => self.wait(),
+        }
+    }
+
+    fn wait(&mut self) {
+        self.attempt += 1;
+        let delay = self.backoff.delay(self.attempt);
+        self.state = State::Waiting(Box::pin(tokio::time::sleep(delay)));
+        self.events.push_back(ReconnectingEvent::Reconnecting {
+            attempt: self.attempt,
+            delay,
+        });
+    }
+
+    /// Sends buffered requests, disconnects on failure.
+    fn poll_send(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let State::Connected(chat) = &mut self.state else {
+            return Poll::Ready(());
+        };
+
+        let result = loop {
+            if self.requests.outbox.is_empty() {
+                break ready!(chat.poll_flush_unpin(cx));
+            }
+
+            if let Err(x) = ready!(chat.poll_ready_unpin(cx)) {
+                break Err(x);
+            }
+
+            // Requests are removed from the outbox only once sent,
+            // so that failed ones are sent again once reconnected.
+            let request = self.requests.outbox.front().cloned();
+            if let Err(x) = chat.start_send_unpin(request.expect("should be buffered")) {
+                break Err(x);
+            }
+
+            self.requests.outbox.pop_front();
+        };
+
+        if let Err(x) = result {
+            self.disconnect(Some(x.to_string()));
+        }
+
+        Poll::Ready(())
+    }
+}
+
+impl Stream for ReconnectingChat {
+    type Item = Result<ReconnectingEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            if let Some(event) = this.events.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+
+            if this.is_connected() {
+                let _ = this.poll_send(cx);
+            }
+
+            match &mut this.state {
+                State::Connected(chat) => match ready!(chat.poll_next_unpin(cx)) {
+                    Some(Ok(message)) => {
+                        this.requests.complete(&message);
+                        return Poll::Ready(Some(Ok(ReconnectingEvent::Message(message))));
+                    }
+                    Some(Err(x @ Error::Decode(_))) => return Poll::Ready(Some(Err(x))),
+                    Some(Err(x)) => this.disconnect(Some(x.to_string())),
+                    None => this.disconnect(None),
+                },
+                State::Waiting(sleep) => {
+                    ready!(sleep.as_mut().poll(cx));
+                    let lang = this.lang.clone();
+                    let router = this.router.clone();
+                    let connect = async move { lang.stream(&router).await };
+                    this.state = State::Connecting(Box::pin(connect));
+                }
+                State::Connecting(connect) => match ready!(connect.as_mut().poll(cx)) {
+                    Ok(chat) => {
+                        let chat = chat.with_timeouts(this.timeouts);
+                        this.state = State::Connected(Box::new(chat));
+                        let (resent, dropped) = this.requests.reconnect(this.resend);
+                        this.events.push_back(ReconnectingEvent::Reconnected {
+                            attempt: this.attempt,
+                            resent,
+                            dropped,
+                        });
+                        this.attempt = 0;
+                    }
+                    Err(x) if this.max_attempts.is_some_and(|max| this.attempt >= max) => {
+                        this.state = State::Closed;
+                        return Poll::Ready(Some(Err(x)));
+                    }
+                    Err(_) => this.wait(),
+                },
+                State::Closed => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl Sink<ChatStreamRequest> for ReconnectingChat {
+    type Error = Error;
+
+    #[inline]
+    fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: ChatStreamRequest) -> Result<(), Self::Error> {
+        if matches!(self.state, State::Closed) {
+            return Err(closed());
+        }
+
+        self.requests.send(item);
+        Ok(())
+    }
+
+    /// Sends buffered requests, returns immediately while disconnected.
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        ready!(self.poll_send(cx));
+        match self.state {
+            State::Closed => Poll::Ready(Err(closed())),
+            _ => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+        match &mut self.state {
+            State::Connected(chat) => chat.poll_close_unpin(cx),
+            _ => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+impl std::fmt::Debug for ReconnectingChat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReconnectingChat")
+            .field("router", &self.router)
+            .field("connected", &self.is_connected())
+            .field("attempt", &self.attempt)
+            .field("backoff", &self.backoff)
+            .field("max_attempts", &self.max_attempts)
+            .field("resend", &self.resend)
+            .field("timeouts", &self.timeouts)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Exponential delay between attempts to reconnect.
+#[derive(Debug, Clone, Copy)]
+struct Backoff {
+    min: Duration,
+    max: Duration,
+}
+
+impl Backoff {
+    fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.min.saturating_mul(factor).min(self.max)
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            min: Duration::from_millis(500),
+            max: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Incomplete requests, in the order they were sent.
+#[derive(Debug, Default)]
+struct Requests {
+    /// All incomplete requests, including the outbox.
+    pending: Vec<ChatStreamRequest>,
+    /// Requests not yet sent over the current connection.
+    outbox: VecDeque<ChatStreamRequest>,
+}
+
+impl Requests {
+    fn send(&mut self, request: ChatStreamRequest) {
+        self.pending.push(request.clone());
+        self.outbox.push_back(request);
+    }
+
+    fn complete(&mut self, message: &ChatStreamMessage) {
+        if message.is_final() {
+            self.pending.retain(|x| x.id != message.id);
+        }
+    }
+
+    /// Returns ids of the resent and the dropped requests, sent over the lost connection.
+    ///
+    /// Requests that were never sent are kept in the outbox either way.
+    fn reconnect(&mut self, resend: bool) -> (Vec<String>, Vec<String>) {
+        let unsent: HashSet<_> = self.outbox.iter().map(|x| x.id.clone()).collect();
+        let sent = self.pending.iter().filter(|x| !unsent.contains(&x.id));
+        let sent = sent.map(|x| x.id.clone()).collect();
+
+        if resend {
+            // Sent requests always precede the unsent ones.
+            self.outbox = self.pending.iter().cloned().collect();
+            return (sent, Vec::new());
+        }
+
+        self.pending.retain(|x| unsent.contains(&x.id));
+        (Vec::new(), sent)
+    }
+}
+
+fn closed() -> Error {
+    let error = "connection is closed after the maximum number of attempts";
+    std::io::Error::new(std::io::ErrorKind::NotConnected, error).into()
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::lang::chat_stream::{ChatStreamMessage, ChatStreamRequest};
+    use crate::lang::reconnect::{Backoff, Requests};
+
+    #[test]
+    fn backoff() {
+        let backoff = Backoff::default();
+        assert_eq!(backoff.delay(1), Duration::from_millis(500));
+        assert_eq!(backoff.delay(3), Duration::from_secs(2));
+        assert_eq!(backoff.delay(10), Duration::from_secs(30));
+        assert_eq!(backoff.delay(100), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn requests() {
+        let mut requests = Requests::default();
+        requests.send(ChatStreamRequest::new("a").with_id("a"));
+        requests.send(ChatStreamRequest::new("b").with_id("b"));
+        requests.outbox.clear();
+        requests.send(ChatStreamRequest::new("c").with_id("c"));
+
+        let end: ChatStreamMessage = serde_json::from_value(json!({
+            "id": "b",
+            "created_at": 0,
+            "router_id": "myrouter",
+            "error": { "name": "error", "message": "failed" },
+        }))
+        .unwrap();
+        requests.complete(&end);
+
+        // Sent while disconnected.
+        requests.send(ChatStreamRequest::new("d").with_id("d"));
+
+        let ids = |x: &Requests| x.outbox.iter().map(|x| x.id.clone()).collect::<Vec<_>>();
+        let pending = |x: &Requests| x.pending.iter().map(|x| x.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&requests), ["c", "d"]);
+
+        let mut dropped = Requests {
+            pending: requests.pending.clone(),
+            outbox: requests.outbox.clone(),
+        };
+        assert_eq!(dropped.reconnect(false), (vec![], vec!["a".to_owned()]));
+        assert_eq!(ids(&dropped), ["c", "d"]);
+        assert_eq!(pending(&dropped), ["c", "d"]);
+
+        assert_eq!(requests.reconnect(true), (vec!["a".to_owned()], vec![]));
+        assert_eq!(ids(&requests), ["a", "c", "d"]);
+        assert_eq!(pending(&requests), ["a", "c", "d"]);
+    }
+}
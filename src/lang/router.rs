@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+#[cfg(feature = "streaming")]
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::lang::chat::{ChatMessage, ChatRequest, ChatRequestOverride, ChatResponse, Role};
+#[cfg(feature = "streaming")]
+use crate::lang::chat_stream::ChatStreamRequest;
+use crate::lang::discovery::UnknownRouter;
+use crate::lang::list::RouterConfig;
+use crate::lang::Language;
+#[cfg(feature = "streaming")]
+use crate::lang::{Chat, Multiplexer, ReconnectingChat};
+#[cfg(feature = "streaming")]
+use crate::types::{Timeout, TimeoutKind};
+use crate::Result;
+
+#[derive(Debug, Clone, Default)]
+struct Defaults {
+    system: Option<ChatMessage>,
+    overrides: HashMap<String, ChatRequestOverride>,
+    timeout: Option<Duration>,
+    tag: Option<String>,
+}
+
+/// Handle of a single `router` with its own request defaults.
+///
+/// Cheap to clone, created with [`Language::router`].
+///
+/// #### Example
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use glide_rs::Client;
+///
+/// # let _ = async {
+/// let glide = Client::default();
+/// let router = glide
+///     .lang
+///     .router("myrouter")
+///     .with_system_prompt("Be concise.")
+///     .with_timeout(Duration::from_secs(30));
+///
+/// let _ = router.chat("Hello!").await?;
+/// # Ok::<_, glide_rs::Error>(())
+/// # };
+/// ```
+#[must_use]
+#[derive(Debug, Clone)]
+pub struct Router {
+    lang: Language,
+    name: Arc<str>,
+    defaults: Arc<Defaults>,
+}
+
+impl Router {
+    /// Creates a new [`Router`] handle.
+    pub(crate) fn new(lang: Language, name: &str) -> Self {
+        Self {
+            lang,
+            name: Arc::from(name),
+            defaults: Arc::default(),
+        }
+    }
+
+    /// Returns the name of the `router`.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Attaches the system prompt, sent before the message history
+    /// of requests without their own system prompt.
+    pub fn with_system_prompt(mut self, prompt: &str) -> Self {
+        let system = ChatMessage::new(prompt).with_system();
+        Arc::make_mut(&mut self.defaults).system = Some(system);
+        self
+    }
+
+    /// Attaches the [`ChatRequestOverride`] of the specified `model`,
+    /// unless the request has its own.
+    pub fn with_override(mut self, model: &str, data: ChatRequestOverride) -> Self {
+        let defaults = Arc::make_mut(&mut self.defaults);
+        defaults.overrides.insert(model.to_owned(), data);
+        self
+    }
+
+    /// Limits the duration of every chat request or `WebSocket` handshake.
+    ///
+    /// Timed out chat requests keep their budget reservation,
+    /// as the gateway may still charge them.
+    ///
+    /// Default value: `None`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        Arc::make_mut(&mut self.defaults).timeout = Some(timeout);
+        self
+    }
+
+    /// Attaches the custom tag, e.g. the feature name, to every chat request.
+    ///
+    /// Not sent to the gateway, used by the [`CostTracker`] and [`Budgets`].
+    ///
+    /// Default value: `None`
+    ///
+    /// [`CostTracker`]: crate::lang::cost::CostTracker
+    /// [`Budgets`]: crate::lang::budget::Budgets
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        Arc::make_mut(&mut self.defaults).tag = Some(tag.to_owned());
+        self
+    }
+
+    /// Returns the [`ChatRequest`] with all defaults applied.
+    pub fn request(&self, data: impl Into<ChatRequest>) -> ChatRequest {
+        let mut data = data.into();
+        if self.defaults.system.is_some() {
+            self.apply_system(data.message_history.get_or_insert_with(Vec::new));
+        }
+
+        self.apply_overrides(&mut data.override_params);
+        data
+    }
+
+    /// Returns the `ChatStreamRequest` with the system prompt and overrides applied.
+    ///
+    /// Requests sent over the connections of [`Router::stream`], [`Router::multiplex`]
+    /// and [`Router::reconnecting`] are sent as is, so should be created with this method.
+    #[cfg(feature = "streaming")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "streaming")))]
+    pub fn stream_request(&self, data: impl Into<ChatStreamRequest>) -> ChatStreamRequest {
+        let mut data = data.into();
+        self.apply_system(&mut data.message_history);
+        self.apply_overrides(&mut data.override_params);
+        data
+    }
+
+    /// Sends a single chat request with all defaults applied.
+    ///
+    /// See [`Language::chat`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the response [`StatusCode`] is not in the 200-299 range
+    /// or the request times out.
+    ///
+    /// [`Error`]: crate::Error
+    /// [`StatusCode`]: reqwest::StatusCode
+    pub async fn chat(&self, data: impl Into<ChatRequest>) -> Result<ChatResponse> {
+        let data = self.request(data);
+        let defaults = &self.defaults;
+        let tag = defaults.tag.as_deref();
+        self.lang
+            .chat_with(&self.name, data, tag, defaults.timeout)
+            .await
+    }
+
+    /// Establishes a `WebSocket` connection for streaming chat messages.
+    ///
+    /// See [`Language::stream`].
+    /// Create requests with [`Router::stream_request`] to apply the defaults.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the response [`StatusCode`] is not in the 200-299 range
+    /// or the handshake times out.
+    ///
+    /// [`Error`]: crate::Error
+    /// [`StatusCode`]: reqwest::StatusCode
+    #[cfg(feature = "streaming")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "streaming")))]
+    pub async fn stream(&self) -> Result<Chat> {
+        self.timeout(self.lang.stream(&self.name)).await
+    }
+
+    /// Establishes a shared `WebSocket` connection for concurrent streaming chats.
+    ///
+    /// See [`Language::multiplex`].
+    /// Create requests with [`Router::stream_request`] to apply the defaults.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the response [`StatusCode`] is not in the 200-299 range
+    /// or the handshake times out.
+    ///
+    /// [`Error`]: crate::Error
+    /// [`StatusCode`]: reqwest::StatusCode
+    #[cfg(feature = "streaming")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "streaming")))]
+    pub async fn multiplex(&self) -> Result<Multiplexer> {
+        self.timeout(self.lang.multiplex(&self.name)).await
+    }
+
+    /// Establishes a `WebSocket` connection that is re-established when lost.
+    ///
+    /// See [`Language::reconnecting`].
+    /// Create requests with [`Router::stream_request`] to apply the defaults.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the response [`StatusCode`] is not in the 200-299 range
+    /// or the handshake times out.
+    ///
+    /// [`Error`]: crate::Error
+    /// [`StatusCode`]: reqwest::StatusCode
+    #[cfg(feature = "streaming")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "streaming")))]
+    pub async fn reconnecting(&self) -> Result<ReconnectingChat> {
+        self.timeout(self.lang.reconnecting(&self.name)).await
+    }
+
+    /// Retrieves the [`RouterConfig`] of the `router`.
+    ///
+    /// Uses the attached `RouterCache`, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the response [`StatusCode`] is not in the 200-299 range
+    /// or the `router` is unknown.
+    ///
+    /// [`Error`]: crate::Error
+    /// [`StatusCode`]: reqwest::StatusCode
+    pub async fn config(&self) -> Result<RouterConfig> {
+        #[cfg(feature = "runtime")]
+        let configs = match &self.lang.0.routers {
+            Some(cache) => cache.get(&self.lang).await?,
+            None => Arc::new(self.lang.list().await?),
+        };
+        #[cfg(not(feature = "runtime"))]
+        let configs = self.lang.list().await?;
+
+        match configs.get(&self.name) {
+            Some(config) => Ok(config.clone()),
+            None => Err(UnknownRouter::new(&self.name, &configs).into()),
+        }
+    }
+
+    /// Inserts the system prompt, unless the history has its own.
+    fn apply_system(&self, history: &mut Vec<ChatMessage>) {
+        if let Some(system) = &self.defaults.system {
+            if !history.iter().any(|x| x.role() == Role::System) {
+                history.insert(0, system.clone());
+            }
+        }
+    }
+
+    /// Inserts the overrides of models without their own.
+    fn apply_overrides(&self, overrides: &mut Option<HashMap<String, ChatRequestOverride>>) {
+        let defaults = &self.defaults;
+        if !defaults.overrides.is_empty() {
+            let overrides = overrides.get_or_insert_with(HashMap::new);
+            for (model, data) in &defaults.overrides {
+                overrides
+                    .entry(model.clone())
+                    .or_insert_with(|| data.clone());
+            }
+        }
+    }
+
+    #[cfg(feature = "streaming")]
+    async fn timeout<T>(&self, future: impl Future<Output = Result<T>>) -> Result<T> {
+        let Some(after) = self.defaults.timeout else {
+            return future.await;
+        };
+
+        match tokio::time::timeout(after, future).await {
+            Ok(result) => result,
+            Err(_) => Err(Timeout {
+                kind: TimeoutKind::Request,
+                after,
+            }
+            .into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lang::chat::{ChatMessage, ChatRequest, ChatRequestOverride, Role};
+    use crate::Client;
+
+    #[test]
+    fn request() {
+        let glide = Client::default();
+        let router = glide
+            .lang
+            .router("myrouter")
+            .with_system_prompt("Be concise.")
+            .with_override("gpt", ChatRequestOverride::new("Hi!"));
+        assert_eq!(router.name(), "myrouter");
+
+        let request = router.request("Hello!");
+        let history = request.message_history.unwrap();
+        assert_eq!(history[0].role(), Role::System);
+        assert!(request.override_params.unwrap().contains_key("gpt"));
+
+        let mut request = ChatRequest::new("Hello!");
+        let system = ChatMessage::new("Be verbose.").with_system();
+        request.message_history = Some(vec![system.clone()]);
+        let request = router.clone().request(request);
+        assert_eq!(request.message_history.unwrap(), [system]);
+    }
+
+    #[test]
+    #[cfg(feature = "streaming")]
+    fn stream_request() {
+        let glide = Client::default();
+        let router = glide
+            .lang
+            .router("myrouter")
+            .with_system_prompt("Be concise.")
+            .with_override("gpt", ChatRequestOverride::new("Hi!"));
+
+        let request = router.stream_request("Hello!");
+        assert_eq!(request.message_history[0].role(), Role::System);
+        assert!(request.override_params.unwrap().contains_key("gpt"));
+    }
+}
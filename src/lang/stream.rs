@@ -1,25 +1,241 @@
+use std::collections::HashSet;
+use std::future::Future;
 use std::pin::Pin;
-use std::task::{ready, Context, Poll};
+use std::task::{Context, Poll};
+use std::time::Duration;
 
 use futures::{Sink, SinkExt, Stream, StreamExt};
 use reqwest_websocket::{CloseCode, Message, WebSocket};
 use serde_json::Value;
+use tokio::time::{Instant, Interval, MissedTickBehavior, Sleep};
 
+use crate::lang::chat_stream::{ChatStreamMessage, ChatStreamRequest, DecodeError};
+use crate::types::{Timeout, TimeoutKind};
 use crate::{Error, Result};
 
+/// Keepalive and timeouts of the [`Chat`].
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Timeouts {
+    pub keepalive: Option<Duration>,
+    pub idle: Option<Duration>,
+    pub chunk: Option<Duration>,
+}
+
 /// Streaming (`WebSocket`) chat connection.
 ///
-/// Implements `futures::`[`Stream`] and `futures::`[`Sink`].
+/// Implements `futures::`[`Stream`] of [`ChatStreamMessage`]s
+/// and `futures::`[`Sink`] of [`ChatStreamRequest`]s.
+///
+/// Use [`Chat::into_raw`] for untyped `JSON` messages.
 #[must_use = "streams do nothing unless you poll them"]
 pub struct Chat {
-    inner: WebSocket,
+    inner: RawChat,
+    chunk: Option<Deadline>,
+    /// Ids of requests with incomplete responses.
+    pending: HashSet<String>,
 }
 
 impl Chat {
     /// Creates a new [`Chat`] connection.
     #[inline]
     pub(crate) fn new(inner: WebSocket) -> Self {
-        Self { inner }
+        RawChat::new(inner).into_typed()
+    }
+
+    /// Sends a `ping` frame every `interval`, keeping the connection
+    /// alive behind proxies and load balancers.
+    ///
+    /// Frames are only sent while the stream is polled, so keep polling it,
+    /// e.g. in a dedicated task, even if no responses are expected.
+    ///
+    /// Default value: `None`
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the `interval` is zero.
+    pub fn with_keepalive(mut self, interval: Duration) -> Self {
+        self.inner = self.inner.with_keepalive(interval);
+        self
+    }
+
+    /// Ends the stream with [`Error::Timeout`] if no frames, including `pong`s,
+    /// are received for the duration.
+    ///
+    /// Default value: `None`
+    ///
+    /// [`Error::Timeout`]: crate::Error::Timeout
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.inner = self.inner.with_idle_timeout(timeout);
+        self
+    }
+
+    /// Ends the stream with [`Error::Timeout`] if no messages are received
+    /// for the duration while any response is incomplete.
+    ///
+    /// Default value: `None`
+    ///
+    /// [`Error::Timeout`]: crate::Error::Timeout
+    pub fn with_chunk_timeout(mut self, timeout: Duration) -> Self {
+        self.chunk = Some(Deadline::new(timeout));
+        self
+    }
+
+    /// Applies all configured [`Timeouts`].
+    pub(crate) fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        if let Some(x) = timeouts.keepalive {
+            self = self.with_keepalive(x);
+        }
+
+        if let Some(x) = timeouts.idle {
+            self = self.with_idle_timeout(x);
+        }
+
+        if let Some(x) = timeouts.chunk {
+            self = self.with_chunk_timeout(x);
+        }
+
+        self
+    }
+
+    /// Returns the underlying untyped connection.
+    #[inline]
+    pub fn into_raw(self) -> RawChat {
+        self.inner
+    }
+
+    /// Closes the underlying connection after sending [`CloseCode::Away`].
+    pub async fn close(self) -> Result<()> {
+        self.inner.close().await
+    }
+}
+
+impl Stream for Chat {
+    type Item = Result<ChatStreamMessage>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        if this.inner.timed_out {
+            return Poll::Ready(None);
+        }
+
+        // Buffered messages are yielded even if the consumer polls late.
+        let Poll::Ready(poll) = this.inner.poll_next_unpin(cx) else {
+            if let Some(chunk) = this.chunk.as_mut().filter(|_| !this.pending.is_empty()) {
+                if chunk.poll(cx).is_ready() {
+                    this.inner.timed_out = true;
+                    return Poll::Ready(Some(Err(chunk.timeout(TimeoutKind::Chunk))));
+                }
+            }
+
+            return Poll::Pending;
+        };
+
+        let next = poll.map(|x| x.and_then(decode));
+        // Requests with invalid messages end with a decode error.
+        let (received, completed) = match &next {
+            Some(Ok(message)) => (true, message.is_final().then_some(&message.id)),
+            Some(Err(Error::Decode(x))) => (true, x.id.as_ref()),
+            _ => (false, None),
+        };
+
+        if let Some(id) = completed {
+            this.pending.remove(id);
+        }
+
+        if let Some(chunk) = this.chunk.as_mut().filter(|_| received) {
+            chunk.reset();
+        }
+
+        Poll::Ready(next)
+    }
+}
+
+impl Sink<ChatStreamRequest> for Chat {
+    type Error = Error;
+
+    #[inline]
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready_unpin(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: ChatStreamRequest) -> Result<(), Self::Error> {
+        if self.pending.is_empty() {
+            if let Some(chunk) = &mut self.chunk {
+                chunk.reset();
+            }
+        }
+
+        self.pending.insert(item.id.clone());
+        let item = serde_json::to_value(item).expect("should be a valid `JSON`");
+        self.inner.start_send_unpin(item)
+    }
+
+    #[inline]
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_flush_unpin(cx)
+    }
+
+    #[inline]
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_close_unpin(cx)
+    }
+}
+
+/// Untyped streaming (`WebSocket`) chat connection.
+///
+/// Implements `futures::`[`Stream`] and `futures::`[`Sink`] of `JSON` values.
+/// Skips `ping` and `pong` frames, ends on the `close` frame.
+#[must_use = "streams do nothing unless you poll them"]
+pub struct RawChat {
+    inner: WebSocket,
+    keepalive: Option<Interval>,
+    /// The `ping` frame is due.
+    ping: bool,
+    idle: Option<Deadline>,
+    timed_out: bool,
+}
+
+impl RawChat {
+    fn new(inner: WebSocket) -> Self {
+        Self {
+            inner,
+            keepalive: None,
+            ping: false,
+            idle: None,
+            timed_out: false,
+        }
+    }
+
+    /// Sends a `ping` frame every `interval`, see [`Chat::with_keepalive`].
+    ///
+    /// Default value: `None`
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the `interval` is zero.
+    pub fn with_keepalive(mut self, interval: Duration) -> Self {
+        let mut interval = tokio::time::interval_at(Instant::now() + interval, interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        self.keepalive = Some(interval);
+        self
+    }
+
+    /// Ends the stream if no frames are received, see [`Chat::with_idle_timeout`].
+    ///
+    /// Default value: `None`
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle = Some(Deadline::new(timeout));
+        self
+    }
+
+    /// Returns the typed connection.
+    #[inline]
+    pub fn into_typed(self) -> Chat {
+        Chat {
+            inner: self,
+            chunk: None,
+            pending: HashSet::new(),
+        }
     }
 
     /// Closes the underlying connection after sending [`CloseCode::Away`].
@@ -27,21 +243,74 @@ impl Chat {
         let response = self.inner.close(CloseCode::Away, None).await;
         response.map_err(Into::into)
     }
+
+    /// Sends the `ping` frame when due.
+    fn poll_ping(&mut self, cx: &mut Context<'_>) -> Result<()> {
+        if let Some(keepalive) = &mut self.keepalive {
+            while keepalive.poll_tick(cx).is_ready() {
+                self.ping = true;
+            }
+        }
+
+        if self.ping {
+            if let Poll::Ready(x) = self.inner.poll_ready_unpin(cx) {
+                x?;
+                self.inner.start_send_unpin(Message::Ping(Vec::new()))?;
+                self.ping = false;
+            }
+        }
+
+        if let Poll::Ready(Err(x)) = self.inner.poll_flush_unpin(cx) {
+            return Err(x.into());
+        }
+
+        Ok(())
+    }
 }
 
-impl Stream for Chat {
+impl Stream for RawChat {
     type Item = Result<Value>;
 
-    #[inline]
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let poll = ready!(self.inner.poll_next_unpin(cx));
-        let next = poll.map(|x| x.and_then(|x| x.json()).map_err(Into::into));
+        if self.timed_out {
+            return Poll::Ready(None);
+        }
 
-        Poll::Ready(next)
+        loop {
+            if let Err(x) = self.poll_ping(cx) {
+                return Poll::Ready(Some(Err(x)));
+            }
+
+            let Poll::Ready(next) = self.inner.poll_next_unpin(cx) else {
+                if let Some(idle) = &mut self.idle {
+                    if idle.poll(cx).is_ready() {
+                        let error = idle.timeout(TimeoutKind::Idle);
+                        self.timed_out = true;
+                        return Poll::Ready(Some(Err(error)));
+                    }
+                }
+
+                return Poll::Pending;
+            };
+
+            if let Some(idle) = &mut self.idle {
+                idle.reset();
+            }
+
+            let next = match next {
+                None | Some(Ok(Message::Close { .. })) => None,
+                #[allow(deprecated)]
+                Some(Ok(Message::Ping(_) | Message::Pong(_))) => continue,
+                Some(Ok(x)) => Some(x.json().map_err(Into::into)),
+                Some(Err(x)) => Some(Err(x.into())),
+            };
+
+            return Poll::Ready(next);
+        }
     }
 }
 
-impl Sink<Value> for Chat {
+impl Sink<Value> for RawChat {
     type Error = Error;
 
     #[inline]
@@ -65,3 +334,43 @@ impl Sink<Value> for Chat {
         self.inner.poll_close_unpin(cx).map_err(Into::into)
     }
 }
+
+/// Resettable timeout.
+struct Deadline {
+    after: Duration,
+    sleep: Pin<Box<Sleep>>,
+}
+
+impl Deadline {
+    fn new(after: Duration) -> Self {
+        Self {
+            after,
+            sleep: Box::pin(tokio::time::sleep(after)),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.sleep.as_mut().reset(Instant::now() + self.after);
+    }
+
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        self.sleep.as_mut().poll(cx)
+    }
+
+    fn timeout(&self, kind: TimeoutKind) -> Error {
+        Timeout {
+            kind,
+            after: self.after,
+        }
+        .into()
+    }
+}
+
+/// Decodes the [`ChatStreamMessage`], keeping the id of invalid ones.
+fn decode(value: Value) -> Result<ChatStreamMessage> {
+    let id = value
+        .get("id")
+        .and_then(Value::as_str)
+        .map(ToOwned::to_owned);
+    serde_json::from_value(value).map_err(|source| DecodeError { id, source }.into())
+}
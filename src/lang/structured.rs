@@ -0,0 +1,121 @@
+//! Structured (`JSON`) output of chat responses.
+//!
+
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+#[cfg(feature = "schema")]
+use crate::lang::chat::{ChatMessage, ChatRequest, Role};
+
+/// Errors that may occur during the parsing of a structured output.
+#[derive(Debug, Error)]
+#[error("failed to parse the response after {attempts} attempt(s): {source}")]
+pub struct StructuredError {
+    /// The content of the last response.
+    pub content: String,
+    /// The number of sent requests.
+    pub attempts: usize,
+    /// The last parsing error.
+    #[source]
+    pub source: serde_json::Error,
+}
+
+/// Returns the `JSON` value embedded in the content.
+///
+/// Prefers the first fenced (```` ``` ````) code block, then the outermost
+/// object or array, then the whole trimmed content.
+#[must_use]
+pub fn extract_json(content: &str) -> &str {
+    if let Some(block) = extract_fenced(content) {
+        return block.trim();
+    }
+
+    let start = content.find(['{', '[']);
+    let end = content.rfind(['}', ']']);
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => &content[start..=end],
+        _ => content.trim(),
+    }
+}
+
+/// Returns the content of the first fenced code block.
+fn extract_fenced(content: &str) -> Option<&str> {
+    let (_, rest) = content.split_once("```")?;
+    // Skips the info string, e.g. ```json.
+    let (_, rest) = rest.split_once('\n')?;
+    let (block, _) = rest.split_once("```")?;
+    Some(block)
+}
+
+/// Extracts and deserializes the `JSON` value embedded in the content.
+///
+/// # Errors
+///
+/// Returns a [`serde_json::Error`] if the value does not match `T`.
+pub fn parse_json<T: DeserializeOwned>(content: &str) -> Result<T, serde_json::Error> {
+    serde_json::from_str(extract_json(content))
+}
+
+/// Adds a [`Role::System`] message with the schema instructions.
+#[cfg(feature = "schema")]
+pub(crate) fn with_schema(mut request: ChatRequest, schema: &str) -> ChatRequest {
+    let instructions = format!(
+        "Respond only with a JSON value matching the following JSON schema, \
+        without any additional text:\n{schema}"
+    );
+
+    let instructions = ChatMessage::new(&instructions).with_system();
+    let history = request.message_history.get_or_insert_with(Vec::new);
+    history.insert(0, instructions);
+    request
+}
+
+/// Moves the request message and the invalid reply into the history
+/// and asks to correct the reply.
+#[cfg(feature = "schema")]
+pub(crate) fn with_correction(
+    mut request: ChatRequest,
+    reply: ChatMessage,
+    error: &serde_json::Error,
+) -> ChatRequest {
+    let correction = format!(
+        "The previous response could not be parsed: {error}. \
+        Respond only with a valid JSON value matching the schema."
+    );
+
+    let message = std::mem::replace(&mut request.message, ChatMessage::new(&correction));
+    let history = request.message_history.get_or_insert_with(Vec::new);
+    history.push(message);
+    history.push(reply.with_role(Role::Assistant));
+    request
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+
+    use crate::lang::structured::{extract_json, parse_json};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[test]
+    fn extract() {
+        let fenced = "Sure!\n```json\n{\"x\": 1}\n```\nAnything else?";
+        assert_eq!(extract_json(fenced), "{\"x\": 1}");
+
+        let bare = "Here it is: [1, 2, 3].";
+        assert_eq!(extract_json(bare), "[1, 2, 3]");
+        assert_eq!(extract_json(" true "), "true");
+    }
+
+    #[test]
+    fn parse() {
+        let point: Point = parse_json("```\n{\"x\": 1, \"y\": 2}\n```").unwrap();
+        assert_eq!(point, Point { x: 1, y: 2 });
+        assert!(parse_json::<Point>("{\"x\": 1}").is_err());
+    }
+}
@@ -0,0 +1,554 @@
+//! Prompt templates rendered into [`ChatMessage`]s and [`ChatRequest`]s.
+//!
+//! #### Syntax
+//!
+//! - `{{name}}` or `{{user.name}}` inserts the variable.
+//! - `{{#if name}}...{{else}}...{{/if}}` renders the section if the variable
+//!   is present and not `null`, `false`, `0`, empty string, array or object.
+//! - `{{#each items}}...{{/each}}` renders the section for every array item,
+//!   available as `{{this}}`, with its position as `{{@index}}`.
+//! - `{{#system}}...{{/system}}`, `{{#user}}...{{/user}}` and
+//!   `{{#assistant}}...{{/assistant}}` define messages of the given role.
+//!   A template without these blocks renders a single user message.
+//! - `{{! comment }}` is ignored.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::lang::chat::{ChatMessage, ChatRequest, Role};
+use crate::Result;
+
+/// Errors that may occur during the parsing or rendering of a [`PromptTemplate`].
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    /// The template is malformed.
+    #[error("syntax error at byte {offset}: {message}")]
+    Syntax { offset: usize, message: String },
+    /// The variable is used but not provided.
+    #[error("missing variable `{0}`")]
+    MissingVariable(String),
+    /// The variable used in `{{#each}}` is not an array.
+    #[error("variable `{0}` is not an array")]
+    NotIterable(String),
+    /// The variables cannot be serialized.
+    #[error("invalid variables: {0}")]
+    Variables(#[from] serde_json::Error),
+    /// The template renders no messages.
+    #[error("template renders no messages")]
+    Empty,
+    /// The template is not a part of [`PromptTemplates`].
+    #[error("unknown template `{0}`")]
+    Unknown(String),
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Text(String),
+    Var(String),
+    If(String, Vec<Node>, Vec<Node>),
+    Each(String, Vec<Node>),
+    Message(Role, Vec<Node>),
+}
+
+/// Prompt template with named variables, conditional sections, loops
+/// and role-tagged messages.
+///
+/// #### Example
+///
+/// ```rust
+/// use glide_rs::lang::template::PromptTemplate;
+/// use serde_json::json;
+///
+/// let template: PromptTemplate = "\
+///     {{#system}}You are a {{persona}}.{{/system}}\
+///     {{#user}}Summarize:{{#each items}} {{this}};{{/each}}{{/user}}"
+///     .parse()?;
+///
+/// let vars = json!({ "persona": "librarian", "items": ["a", "b"] });
+/// let request = template.render_request(&vars)?;
+/// assert_eq!(request.message.content, "Summarize: a; b;");
+/// # Ok::<_, glide_rs::Error>(())
+/// ```
+#[must_use]
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    nodes: Vec<Node>,
+}
+
+impl PromptTemplate {
+    /// Parses a new [`PromptTemplate`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the template is malformed.
+    ///
+    /// [`Error`]: crate::Error
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut parser = Parser {
+            source,
+            offset: 0,
+            top_level: true,
+            stray: None,
+        };
+
+        let (nodes, end) = parser.nodes()?;
+        if let Some((tag, offset)) = end {
+            return Err(syntax(offset, format!("unexpected `{{{{{tag}}}}}`")));
+        }
+
+        let messages = nodes.iter().any(|x| matches!(x, Node::Message(..)));
+        if let Some(offset) = parser.stray.filter(|_| messages) {
+            let message = "content outside of a message block".to_owned();
+            return Err(syntax(offset, message));
+        }
+
+        Ok(Self { nodes })
+    }
+
+    /// Reads and parses a new [`PromptTemplate`] from the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the file cannot be read or the template is malformed.
+    ///
+    /// [`Error`]: crate::Error
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let source = std::fs::read_to_string(path)?;
+        Self::parse(&source)
+    }
+
+    /// Renders the template into messages.
+    ///
+    /// Message contents are trimmed, empty messages are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if a used variable is missing or not an array for `{{#each}}`.
+    ///
+    /// [`Error`]: crate::Error
+    pub fn render(&self, vars: &impl Serialize) -> Result<Vec<ChatMessage>> {
+        let vars = serde_json::to_value(vars).map_err(TemplateError::from)?;
+        let mut scope = Scope {
+            stack: vec![(&vars, None)],
+        };
+
+        let messages = self.nodes.iter().filter_map(|x| match x {
+            Node::Message(role, nodes) => Some((*role, nodes.as_slice())),
+            _ => None,
+        });
+
+        let mut messages: Vec<_> = messages.collect();
+        if messages.is_empty() {
+            messages.push((Role::User, self.nodes.as_slice()));
+        }
+
+        let mut rendered = Vec::with_capacity(messages.len());
+        for (role, nodes) in messages {
+            let mut content = String::new();
+            scope.render(nodes, &mut content)?;
+
+            let content = content.trim();
+            if !content.is_empty() {
+                rendered.push(ChatMessage::new(content).with_role(role));
+            }
+        }
+
+        Ok(rendered)
+    }
+
+    /// Renders the template into a [`ChatRequest`].
+    ///
+    /// The last message becomes [`ChatRequest::message`],
+    /// all previous messages become [`ChatRequest::message_history`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if a used variable is missing or no messages are rendered.
+    ///
+    /// [`Error`]: crate::Error
+    pub fn render_request(&self, vars: &impl Serialize) -> Result<ChatRequest> {
+        let mut messages = self.render(vars)?;
+        let message = messages.pop().ok_or(TemplateError::Empty)?;
+
+        let mut request = ChatRequest::new(message);
+        if !messages.is_empty() {
+            request.message_history = Some(messages);
+        }
+
+        Ok(request)
+    }
+}
+
+impl FromStr for PromptTemplate {
+    type Err = crate::Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+/// Named [`PromptTemplate`]s, e.g. loaded from a directory.
+#[derive(Debug, Clone, Default)]
+pub struct PromptTemplates {
+    templates: HashMap<String, PromptTemplate>,
+}
+
+impl PromptTemplates {
+    /// Creates a new empty [`PromptTemplates`].
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads all files of the directory, named after their file stem,
+    /// e.g. `summarize.prompt` as `summarize`. Hidden files are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if a file cannot be read or a template is malformed.
+    ///
+    /// [`Error`]: crate::Error
+    pub fn from_dir(path: impl AsRef<Path>) -> Result<Self> {
+        let mut templates = Self::new();
+        for entry in std::fs::read_dir(path)? {
+            let path = entry?.path();
+            let Some(name) = path.file_stem().and_then(|x| x.to_str()) else {
+                continue;
+            };
+
+            if path.is_file() && !name.starts_with('.') {
+                let name = name.to_owned();
+                templates.insert(&name, PromptTemplate::from_file(&path)?);
+            }
+        }
+
+        Ok(templates)
+    }
+
+    /// Inserts the template, replacing the template with the same name.
+    #[inline]
+    pub fn insert(&mut self, name: &str, template: PromptTemplate) {
+        self.templates.insert(name.to_owned(), template);
+    }
+
+    /// Returns the reference to the named template.
+    #[inline]
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&PromptTemplate> {
+        self.templates.get(name)
+    }
+
+    /// Returns an iterator over all template names.
+    #[inline]
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.templates.keys().map(String::as_str)
+    }
+
+    /// Renders the named template into a [`ChatRequest`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the template is unknown or cannot be rendered.
+    ///
+    /// [`Error`]: crate::Error
+    pub fn render_request(&self, name: &str, vars: &impl Serialize) -> Result<ChatRequest> {
+        let template = self.get(name);
+        let template = template.ok_or_else(|| TemplateError::Unknown(name.to_owned()))?;
+        template.render_request(vars)
+    }
+}
+
+fn syntax(offset: usize, message: String) -> crate::Error {
+    TemplateError::Syntax { offset, message }.into()
+}
+
+/// Closing (`/..`) or `else` tag with its offset.
+type EndTag = Option<(String, usize)>;
+
+struct Parser<'a> {
+    source: &'a str,
+    offset: usize,
+    top_level: bool,
+    /// Offset of the first top-level content that is not a message block.
+    stray: Option<usize>,
+}
+
+impl Parser<'_> {
+    /// Parses nodes until the end of the source or the [`EndTag`].
+    fn nodes(&mut self) -> Result<(Vec<Node>, EndTag)> {
+        let mut nodes = Vec::new();
+        loop {
+            let rest = &self.source[self.offset..];
+            let Some(start) = rest.find("{{") else {
+                if !rest.is_empty() {
+                    self.text(self.offset, rest);
+                    nodes.push(Node::Text(rest.to_owned()));
+                }
+
+                self.offset = self.source.len();
+                return Ok((nodes, None));
+            };
+
+            if start > 0 {
+                self.text(self.offset, &rest[..start]);
+                nodes.push(Node::Text(rest[..start].to_owned()));
+            }
+
+            let offset = self.offset + start;
+            let Some(end) = rest[start..].find("}}") else {
+                return Err(syntax(offset, "unclosed `{{`".to_owned()));
+            };
+
+            let tag = rest[start + 2..start + end].trim();
+            self.offset = offset + end + 2;
+
+            if tag.starts_with('!') {
+                continue;
+            } else if tag == "else" || tag.starts_with('/') {
+                return Ok((nodes, Some((tag.to_owned(), offset))));
+            } else if let Some(block) = tag.strip_prefix('#') {
+                nodes.push(self.block(block.trim(), offset)?);
+            } else if tag.is_empty() {
+                return Err(syntax(offset, "empty tag".to_owned()));
+            } else {
+                self.mark_stray(offset);
+                nodes.push(Node::Var(tag.to_owned()));
+            }
+        }
+    }
+
+    /// Marks the top-level text as stray unless it is whitespace.
+    fn text(&mut self, offset: usize, text: &str) {
+        let trimmed = text.trim_start();
+        if !trimmed.is_empty() {
+            self.mark_stray(offset + text.len() - trimmed.len());
+        }
+    }
+
+    fn mark_stray(&mut self, offset: usize) {
+        if self.top_level {
+            self.stray.get_or_insert(offset);
+        }
+    }
+
+    /// Parses the section of the block up to its closing tag.
+    fn section(&mut self, name: &str, offset: usize) -> Result<(Vec<Node>, bool)> {
+        let (nodes, end) = self.nodes()?;
+        match end {
+            Some((tag, _)) if tag == "else" && name == "if" => Ok((nodes, true)),
+            Some((tag, _)) if tag.strip_prefix('/').map(str::trim) == Some(name) => {
+                Ok((nodes, false))
+            }
+            Some((tag, offset)) => Err(syntax(offset, format!("unexpected `{{{{{tag}}}}}`"))),
+            None => Err(syntax(offset, format!("unclosed `{{{{#{name}}}}}`"))),
+        }
+    }
+
+    fn block(&mut self, block: &str, offset: usize) -> Result<Node> {
+        let (name, arg) = block.split_once(' ').unwrap_or((block, ""));
+        let arg = arg.trim().to_owned();
+
+        let role = match name {
+            "system" => Some(Role::System),
+            "user" => Some(Role::User),
+            "assistant" => Some(Role::Assistant),
+            _ => None,
+        };
+
+        if let Some(role) = role {
+            if !self.top_level {
+                let message = format!("nested `{{{{#{name}}}}}`");
+                return Err(syntax(offset, message));
+            }
+
+            self.top_level = false;
+            let (nodes, _) = self.section(name, offset)?;
+            self.top_level = true;
+            return Ok(Node::Message(role, nodes));
+        }
+
+        if arg.is_empty() {
+            let message = format!("missing variable of `{{{{#{name}}}}}`");
+            return Err(syntax(offset, message));
+        }
+
+        self.mark_stray(offset);
+        let top_level = std::mem::replace(&mut self.top_level, false);
+        let node = match name {
+            "if" => {
+                let (then, has_else) = self.section(name, offset)?;
+                let otherwise = match has_else {
+                    true => self.section(name, offset)?,
+                    false => (Vec::new(), false),
+                };
+
+                if otherwise.1 {
+                    return Err(syntax(offset, "duplicate `{{else}}`".to_owned()));
+                }
+
+                Node::If(arg, then, otherwise.0)
+            }
+            "each" => Node::Each(arg, self.section(name, offset)?.0),
+            _ => return Err(syntax(offset, format!("unknown block `{name}`"))),
+        };
+
+        self.top_level = top_level;
+        Ok(node)
+    }
+}
+
+struct Scope<'a> {
+    /// Root variables and `{{#each}}` items with their index.
+    stack: Vec<(&'a Value, Option<usize>)>,
+}
+
+impl<'a> Scope<'a> {
+    fn lookup(&self, path: &str) -> Option<Value> {
+        if path == "@index" {
+            let index = self.stack.iter().rev().find_map(|x| x.1)?;
+            return Some(Value::from(index));
+        }
+
+        let mut keys = path.split('.');
+        let first = keys.next()?;
+
+        let mut value = if first == "this" {
+            self.stack.last()?.0
+        } else {
+            let mut scopes = self.stack.iter().rev();
+            scopes.find_map(|x| x.0.get(first))?
+        };
+
+        for key in keys {
+            value = match value {
+                Value::Array(x) => x.get(key.parse::<usize>().ok()?)?,
+                x => x.get(key)?,
+            };
+        }
+
+        Some(value.clone())
+    }
+
+    fn render(&mut self, nodes: &'a [Node], output: &mut String) -> Result<()> {
+        for node in nodes {
+            match node {
+                Node::Text(x) => output.push_str(x),
+                Node::Var(path) => match self.lookup(path) {
+                    None => return Err(TemplateError::MissingVariable(path.clone()).into()),
+                    Some(Value::Null) => {}
+                    Some(Value::String(x)) => output.push_str(&x),
+                    Some(x) => output.push_str(&x.to_string()),
+                },
+                Node::If(path, then, otherwise) => {
+                    let truthy = match self.lookup(path) {
+                        None | Some(Value::Null | Value::Bool(false)) => false,
+                        Some(Value::Number(x)) => x.as_f64() != Some(0.0),
+                        Some(Value::String(x)) => !x.is_empty(),
+                        Some(Value::Array(x)) => !x.is_empty(),
+                        Some(Value::Object(x)) => !x.is_empty(),
+                        Some(Value::Bool(true)) => true,
+                    };
+
+                    self.render(if truthy { then } else { otherwise }, output)?;
+                }
+                Node::Each(path, body) => {
+                    let items = match self.lookup(path) {
+                        None => return Err(TemplateError::MissingVariable(path.clone()).into()),
+                        Some(Value::Array(x)) => x,
+                        Some(_) => return Err(TemplateError::NotIterable(path.clone()).into()),
+                    };
+
+                    for (index, item) in items.iter().enumerate() {
+                        // Items are cloned out of the variables, so the scope is rebuilt.
+                        let mut scope = Scope {
+                            stack: self.stack.clone(),
+                        };
+
+                        scope.stack.push((item, Some(index)));
+                        scope.render(body, output)?;
+                    }
+                }
+                Node::Message(..) => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::fixtures::TempDir;
+    use crate::lang::chat::Role;
+    use crate::lang::template::{PromptTemplate, PromptTemplates, TemplateError};
+    use crate::Error;
+
+    #[test]
+    fn render() {
+        let template = PromptTemplate::parse(
+            "{{#system}}\nYou are {{bot.name}}.{{#if strict}} Be strict.{{else}} Be kind.{{/if}}\n{{/system}}\n\
+            {{#user}}\n{{#each items}}{{@index}}: {{this.text}}\n{{/each}}{{/user}}",
+        )
+        .unwrap();
+
+        let vars = json!({
+            "bot": { "name": "Glide" },
+            "strict": false,
+            "items": [{ "text": "a" }, { "text": "b" }],
+        });
+
+        let messages = template.render(&vars).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role(), Role::System);
+        assert_eq!(messages[0].content, "You are Glide. Be kind.");
+        assert_eq!(messages[1].content, "0: a\n1: b");
+    }
+
+    #[test]
+    fn render_request() {
+        let template = PromptTemplate::parse("Hello, {{name}}!{{! greeting }}").unwrap();
+        let request = template
+            .render_request(&json!({ "name": "Glide" }))
+            .unwrap();
+        assert_eq!(request.message.content, "Hello, Glide!");
+        assert!(request.message_history.is_none());
+    }
+
+    #[test]
+    fn errors() {
+        let template = PromptTemplate::parse("Hello, {{name}}!").unwrap();
+        let error = template.render(&json!({})).unwrap_err();
+        assert!(matches!(error, Error::Template(TemplateError::MissingVariable(x)) if x == "name"));
+
+        assert!(PromptTemplate::parse("{{#if x}}").is_err());
+        assert!(PromptTemplate::parse("{{#each x}}{{/if}}").is_err());
+        assert!(PromptTemplate::parse("{{#user}}{{#system}}{{/system}}{{/user}}").is_err());
+        assert!(PromptTemplate::parse("Hi!{{#user}}{{/user}}").is_err());
+
+        let error = PromptTemplate::parse("{{#user}}Hi!{{/user}}\n {{name}}").unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Template(TemplateError::Syntax { offset: 23, .. })
+        ));
+    }
+
+    #[test]
+    fn from_dir() {
+        let dir = TempDir::new("templates");
+        std::fs::write(dir.path().join("hello.prompt"), "Hello, {{name}}!").unwrap();
+        std::fs::write(dir.path().join(".hidden.prompt"), "{{").unwrap();
+        std::fs::create_dir(dir.path().join("nested.prompt")).unwrap();
+
+        let templates = PromptTemplates::from_dir(dir.path()).unwrap();
+        assert_eq!(templates.names().collect::<Vec<_>>(), ["hello"]);
+    }
+}
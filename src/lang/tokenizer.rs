@@ -0,0 +1,196 @@
+//! Local token estimation for [`ChatMessage`]s and [`ChatRequest`]s.
+//!
+
+use crate::lang::chat::{ChatMessage, ChatRequest, Role, TokenUsage};
+use crate::lang::content::{Content, ContentPart};
+
+/// Tokens added to every message by the chat format.
+const TOKENS_PER_MESSAGE: usize = 3;
+/// Tokens added to every message with the author name.
+const TOKENS_PER_NAME: usize = 1;
+/// Tokens added to every request to prime the reply.
+const TOKENS_PER_REPLY: usize = 3;
+/// Tokens of a single image, as of a low-detail image.
+const TOKENS_PER_IMAGE: usize = 85;
+
+/// Token estimation strategy.
+///
+/// BPE vocabularies require the `tokenizer` feature.
+#[must_use]
+#[non_exhaustive]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Tokenizer {
+    /// Roughly 4 characters per token, no vocabulary required.
+    #[default]
+    Heuristic,
+    /// `cl100k_base` vocabulary, used by `gpt-4` and `gpt-3.5-turbo`.
+    #[cfg(feature = "tokenizer")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokenizer")))]
+    Cl100k,
+    /// `o200k_base` vocabulary, used by `gpt-4o` and `o1`.
+    #[cfg(feature = "tokenizer")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokenizer")))]
+    O200k,
+}
+
+impl Tokenizer {
+    /// Returns the [`Tokenizer`] best matching the `model` name.
+    ///
+    /// Falls back to [`Tokenizer::Heuristic`] for unknown models.
+    pub fn for_model(model: &str) -> Self {
+        #[cfg(feature = "tokenizer")]
+        {
+            let o200k = ["gpt-4o", "gpt-4.1", "o1", "o3", "o4"];
+            if o200k.iter().any(|x| model.starts_with(x)) {
+                return Self::O200k;
+            }
+
+            let cl100k = ["gpt-4", "gpt-3.5", "text-embedding-"];
+            if cl100k.iter().any(|x| model.starts_with(x)) {
+                return Self::Cl100k;
+            }
+        }
+
+        let _ = model;
+        Self::Heuristic
+    }
+
+    /// Returns the estimated number of tokens of the text.
+    #[must_use]
+    pub fn count(&self, text: &str) -> usize {
+        match self {
+            Self::Heuristic => text.chars().count().div_ceil(4),
+            #[cfg(feature = "tokenizer")]
+            Self::Cl100k => {
+                let bpe = tiktoken_rs::cl100k_base_singleton();
+                bpe.encode_with_special_tokens(text).len()
+            }
+            #[cfg(feature = "tokenizer")]
+            Self::O200k => {
+                let bpe = tiktoken_rs::o200k_base_singleton();
+                bpe.encode_with_special_tokens(text).len()
+            }
+        }
+    }
+
+    /// Returns the estimated number of tokens of the message, including the chat format.
+    #[must_use]
+    pub fn count_message(&self, message: &ChatMessage) -> usize {
+        let role = match message.role() {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+            Role::Tool => "tool",
+        };
+
+        let name = message
+            .name
+            .as_deref()
+            .map_or(0, |x| self.count(x) + TOKENS_PER_NAME);
+
+        TOKENS_PER_MESSAGE + self.count(role) + self.count_content(&message.content) + name
+    }
+
+    /// Returns the estimated number of tokens of the content.
+    ///
+    /// Every image is estimated as a fixed number of tokens.
+    #[must_use]
+    pub fn count_content(&self, content: &Content) -> usize {
+        let parts = match content {
+            Content::Text(x) => return self.count(x),
+            Content::Parts(x) => x.iter(),
+        };
+
+        parts
+            .map(|x| match x {
+                ContentPart::Text(x) => self.count(x),
+                ContentPart::ImageUrl { .. } | ContentPart::Image { .. } => TOKENS_PER_IMAGE,
+            })
+            .sum()
+    }
+
+    /// Returns the estimated number of prompt tokens of the request.
+    #[must_use]
+    pub fn count_request(&self, request: &ChatRequest) -> usize {
+        let history = request.message_history.iter().flatten();
+        let history: usize = history.map(|x| self.count_message(x)).sum();
+        history + self.count_message(&request.message) + TOKENS_PER_REPLY
+    }
+
+    /// Compares the estimated prompt size of the request with the actual [`TokenUsage`].
+    pub fn compare(&self, request: &ChatRequest, usage: &TokenUsage) -> Estimate {
+        Estimate {
+            estimated: self.count_request(request),
+            actual: usize::try_from(usage.prompt_tokens).unwrap_or_default(),
+        }
+    }
+}
+
+/// Estimated and actual number of prompt tokens.
+#[must_use]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Estimate {
+    pub estimated: usize,
+    pub actual: usize,
+}
+
+impl Estimate {
+    /// Returns the signed difference between the estimated and the actual number of tokens.
+    #[inline]
+    #[must_use]
+    pub fn error(&self) -> i64 {
+        let estimated = i64::try_from(self.estimated).unwrap_or(i64::MAX);
+        let actual = i64::try_from(self.actual).unwrap_or(i64::MAX);
+        estimated - actual
+    }
+
+    /// Returns the ratio of the estimated to the actual number of tokens.
+    ///
+    /// Returns `None` if the actual number of tokens is zero.
+    #[inline]
+    #[must_use]
+    pub fn ratio(&self) -> Option<f64> {
+        #[allow(clippy::cast_precision_loss)]
+        (self.actual != 0).then(|| self.estimated as f64 / self.actual as f64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lang::chat::{ChatMessage, ChatRequest, TokenUsage};
+    use crate::lang::tokenizer::Tokenizer;
+
+    #[test]
+    fn heuristic() {
+        let tokenizer = Tokenizer::Heuristic;
+        assert_eq!(tokenizer.count(""), 0);
+        assert_eq!(tokenizer.count("Hello!"), 2);
+
+        let message = ChatMessage::new("Hello!");
+        assert_eq!(tokenizer.count_message(&message), 3 + 1 + 2);
+    }
+
+    #[test]
+    fn compare() {
+        let tokenizer = Tokenizer::Heuristic;
+        let request = ChatRequest::new("Hello!");
+        let usage = TokenUsage {
+            prompt_tokens: 10,
+            response_tokens: 5,
+            total_tokens: 15,
+        };
+
+        let estimate = tokenizer.compare(&request, &usage);
+        assert_eq!(estimate.estimated, 9);
+        assert_eq!(estimate.error(), -1);
+    }
+
+    #[test]
+    #[cfg(feature = "tokenizer")]
+    fn bpe() {
+        assert_eq!(Tokenizer::for_model("gpt-4o-mini"), Tokenizer::O200k);
+        assert_eq!(Tokenizer::for_model("gpt-4-turbo"), Tokenizer::Cl100k);
+        assert_eq!(Tokenizer::Cl100k.count("Hello, world!"), 4);
+        assert_eq!(Tokenizer::O200k.count("Hello, world!"), 4);
+    }
+}
@@ -0,0 +1,316 @@
+//! Tool (function) calling types and the execution loop.
+//!
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::lang::chat::{ChatMessage, ChatRequest, ChatResponse, Role};
+use crate::lang::Language;
+use crate::{Error, Result};
+
+/// The type of the tool.
+#[must_use]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ToolKind {
+    #[default]
+    #[serde(rename = "function")]
+    Function,
+}
+
+/// Tool the model may call.
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tool {
+    #[serde(rename = "type")]
+    pub kind: ToolKind,
+    pub function: FunctionDefinition,
+}
+
+impl Tool {
+    /// Creates a new function [`Tool`].
+    ///
+    /// `parameters` is a `JSON` schema object describing the function arguments.
+    pub fn function(name: &str, description: &str, parameters: Value) -> Self {
+        Self {
+            kind: ToolKind::Function,
+            function: FunctionDefinition {
+                name: name.to_owned(),
+                description: Some(description.to_owned()),
+                parameters,
+            },
+        }
+    }
+}
+
+/// Name, description and parameters of the function.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionDefinition {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub parameters: Value,
+}
+
+/// Tool call requested by the model.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: ToolKind,
+    pub function: FunctionCall,
+}
+
+/// Name and `JSON`-encoded arguments of the called function.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionCall {
+    pub name: String,
+    pub arguments: String,
+}
+
+type ToolFuture = Pin<Box<dyn Future<Output = Result<Value, String>> + Send>>;
+type ToolHandler = Box<dyn Fn(Value) -> ToolFuture + Send + Sync>;
+
+/// Executes tool calls requested by the model until it returns the final answer.
+///
+/// #### Example
+///
+/// ```rust,no_run
+/// use glide_rs::lang::chat::ChatRequest;
+/// use glide_rs::lang::tools::ToolRunner;
+/// use glide_rs::Client;
+/// use serde_json::{json, Value};
+///
+/// # let _ = async {
+/// let glide = Client::default();
+/// let runner = ToolRunner::new().register(
+///     "get_time",
+///     "Returns the current UNIX time.",
+///     json!({ "type": "object", "properties": {} }),
+///     |_: Value| async { Ok::<_, String>(json!(1_700_000_000)) },
+/// );
+///
+/// let request = ChatRequest::new("What time is it?");
+/// let _ = runner.run(&glide.lang, "myrouter", request).await?;
+/// # Ok::<_, glide_rs::Error>(())
+/// # };
+/// ```
+#[must_use]
+pub struct ToolRunner {
+    tools: Vec<Tool>,
+    handlers: HashMap<String, ToolHandler>,
+    max_steps: usize,
+}
+
+impl ToolRunner {
+    /// Creates a new [`ToolRunner`] without tools.
+    pub fn new() -> Self {
+        Self {
+            tools: Vec::new(),
+            handlers: HashMap::new(),
+            max_steps: 8,
+        }
+    }
+
+    /// Overrides the maximum number of chat requests per [`ToolRunner::run`].
+    ///
+    /// Default value: `8`
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the `max_steps` is zero.
+    pub const fn with_max_steps(mut self, max_steps: usize) -> Self {
+        assert!(max_steps > 0, "`max_steps` must be non-zero");
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Registers the async function handler.
+    ///
+    /// The handler receives the parsed arguments, its output or error
+    /// is sent back to the model.
+    pub fn register<F, Fut, E>(
+        mut self,
+        name: &str,
+        description: &str,
+        parameters: Value,
+        handler: F,
+    ) -> Self
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value, E>> + Send + 'static,
+        E: fmt::Display,
+    {
+        let handler: ToolHandler = Box::new(move |args| {
+            let future = handler(args);
+            Box::pin(async move { future.await.map_err(|x| x.to_string()) })
+        });
+
+        self.tools.retain(|x| x.function.name != name);
+        self.tools
+            .push(Tool::function(name, description, parameters));
+        self.handlers.insert(name.to_owned(), handler);
+        self
+    }
+
+    /// Returns the definitions of all registered tools.
+    #[inline]
+    pub fn tools(&self) -> &[Tool] {
+        &self.tools
+    }
+
+    /// Executes a single tool call and returns the [`Role::Tool`] message with its result.
+    pub async fn call(&self, call: &ToolCall) -> ChatMessage {
+        let output = match self.handlers.get(&call.function.name) {
+            None => Err(format!("unknown tool `{}`", call.function.name)),
+            Some(handler) => match serde_json::from_str(&call.function.arguments) {
+                Ok(args) => handler(args).await,
+                Err(error) => Err(format!("invalid arguments: {error}")),
+            },
+        };
+
+        let content = match output {
+            Ok(Value::String(x)) => x,
+            Ok(x) => x.to_string(),
+            Err(x) => serde_json::json!({ "error": x }).to_string(),
+        };
+
+        let mut message = ChatMessage::new(&content).with_role(Role::Tool);
+        message.tool_call_id = Some(call.id.clone());
+        message
+    }
+
+    /// Sends the request with all registered tools to a specified `router`,
+    /// executes the requested tool calls sequentially and sends their results back,
+    /// until the model responds without tool calls.
+    ///
+    /// Tools of the request are kept, except those with the name of a registered tool.
+    /// Calls of tools without a handler are answered with an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the response [`StatusCode`] is not in the 200-299 range
+    /// or the model still requests tool calls after the maximum number of steps.
+    ///
+    /// [`StatusCode`]: reqwest::StatusCode
+    pub async fn run(
+        &self,
+        lang: &Language,
+        router: &str,
+        mut data: ChatRequest,
+    ) -> Result<ChatResponse> {
+        self.attach(&mut data);
+
+        for _ in 0..self.max_steps {
+            let response = lang.chat(router, data.clone()).await?;
+            let calls = response.model_response.tool_calls();
+            if calls.is_empty() {
+                return Ok(response);
+            }
+
+            let mut results = Vec::with_capacity(calls.len());
+            for call in calls {
+                results.push(self.call(call).await);
+            }
+
+            let reply = response.model_response.message.with_role(Role::Assistant);
+            let message = results.pop().expect("should not be empty");
+
+            let previous = std::mem::replace(&mut data.message, message);
+            let history = data.message_history.get_or_insert_with(Vec::new);
+            history.push(previous);
+            history.push(reply);
+            history.extend(results);
+        }
+
+        Err(Error::ToolStepLimit(self.max_steps))
+    }
+
+    /// Adds all registered tools to the request, replacing those with the same name.
+    fn attach(&self, data: &mut ChatRequest) {
+        let tools = data.tools.get_or_insert_with(Vec::new);
+        tools.retain(|x| !self.handlers.contains_key(&x.function.name));
+        tools.extend(self.tools.iter().cloned());
+    }
+}
+
+impl Default for ToolRunner {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ToolRunner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ToolRunner")
+            .field("tools", &self.tools)
+            .field("max_steps", &self.max_steps)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{json, Value};
+
+    use crate::lang::chat::{ChatRequest, Role};
+    use crate::lang::tools::{FunctionCall, Tool, ToolCall, ToolKind, ToolRunner};
+
+    fn call(name: &str, arguments: &str) -> ToolCall {
+        ToolCall {
+            id: "call_1".to_owned(),
+            kind: ToolKind::Function,
+            function: FunctionCall {
+                name: name.to_owned(),
+                arguments: arguments.to_owned(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn call_tool() {
+        let runner = ToolRunner::new().register(
+            "add",
+            "Adds two numbers.",
+            json!({ "type": "object" }),
+            |args: Value| async move {
+                let sum = args["a"].as_i64().zip(args["b"].as_i64());
+                sum.map(|(a, b)| json!(a + b)).ok_or("missing arguments")
+            },
+        );
+
+        let message = runner.call(&call("add", r#"{"a": 1, "b": 2}"#)).await;
+        assert_eq!(message.role(), Role::Tool);
+        assert_eq!(message.tool_call_id.as_deref(), Some("call_1"));
+        assert_eq!(message.content, "3");
+
+        let message = runner.call(&call("add", "{}")).await;
+        assert_eq!(message.content, r#"{"error":"missing arguments"}"#);
+
+        let message = runner.call(&call("sub", "{}")).await;
+        assert_eq!(message.content, r#"{"error":"unknown tool `sub`"}"#);
+
+        let mut request = ChatRequest::new("Hello!");
+        let parameters = json!({ "type": "object" });
+        request.tools = Some(vec![
+            Tool::function("add", "Stale.", parameters.clone()),
+            Tool::function("sub", "Subtracts two numbers.", parameters),
+        ]);
+
+        runner.attach(&mut request);
+        let tools = request.tools.unwrap();
+        let tools: Vec<_> = tools.iter().map(|x| x.function.name.as_str()).collect();
+        assert_eq!(tools, ["sub", "add"]);
+    }
+
+    #[test]
+    #[should_panic = "`max_steps` must be non-zero"]
+    fn zero_steps() {
+        let _ = ToolRunner::new().with_max_steps(0);
+    }
+}
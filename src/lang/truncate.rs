@@ -0,0 +1,281 @@
+//! Truncation policies for [`ChatRequest::message_history`].
+//!
+
+use std::fmt;
+
+use crate::lang::chat::{ChatMessage, ChatRequest, Role};
+use crate::lang::tokenizer::Tokenizer;
+
+/// Policy deciding which messages of the history are sent to the model.
+///
+/// Policies are applied by [`Language::chat`] before sending, see
+/// [`Builder::with_truncation`] and [`Builder::with_router_truncation`].
+///
+/// [`Language::chat`]: crate::lang::Language::chat
+/// [`Builder::with_truncation`]: crate::Builder::with_truncation
+/// [`Builder::with_router_truncation`]: crate::Builder::with_router_truncation
+pub trait TruncationPolicy: fmt::Debug + Send + Sync {
+    /// Truncates the message history and returns the dropped messages.
+    fn truncate(&self, request: &mut ChatRequest) -> Vec<ChatMessage> {
+        self.truncate_pinned(request, &[])
+    }
+
+    /// Truncates the message history and returns the dropped messages.
+    ///
+    /// `pinned` messages are removed from the history by the caller
+    /// and sent regardless, but still occupy the context window.
+    fn truncate_pinned(
+        &self,
+        request: &mut ChatRequest,
+        pinned: &[ChatMessage],
+    ) -> Vec<ChatMessage>;
+}
+
+/// Keeps the last `N` messages of the history.
+///
+/// Tool results are dropped together with their tool call.
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepLast(pub usize);
+
+impl TruncationPolicy for KeepLast {
+    fn truncate_pinned(&self, request: &mut ChatRequest, _: &[ChatMessage]) -> Vec<ChatMessage> {
+        let Some(history) = request.message_history.as_mut() else {
+            return Vec::new();
+        };
+
+        let split = skip_results(history, history.len().saturating_sub(self.0));
+        history.drain(..split).collect()
+    }
+}
+
+/// Keeps the first `head` and the last `tail` messages, dropping the middle turns.
+///
+/// Tool calls and their results are kept or dropped together.
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DropMiddle {
+    pub head: usize,
+    pub tail: usize,
+}
+
+impl DropMiddle {
+    /// Creates a new [`DropMiddle`] policy.
+    #[inline]
+    pub const fn new(head: usize, tail: usize) -> Self {
+        Self { head, tail }
+    }
+}
+
+impl TruncationPolicy for DropMiddle {
+    fn truncate_pinned(&self, request: &mut ChatRequest, _: &[ChatMessage]) -> Vec<ChatMessage> {
+        let Some(history) = request.message_history.as_mut() else {
+            return Vec::new();
+        };
+
+        if history.len() <= self.head + self.tail {
+            return Vec::new();
+        }
+
+        let start = skip_results(history, self.head);
+        let end = skip_results(history, history.len() - self.tail);
+        if start >= end {
+            return Vec::new();
+        }
+
+        history.drain(start..end).collect()
+    }
+}
+
+/// Drops the oldest messages until the request fits into the token budget.
+///
+/// The budget accounts for the request message and pinned messages,
+/// which are never dropped. Tool results are dropped together with their tool call.
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenBudget {
+    pub max_tokens: usize,
+    pub tokenizer: Tokenizer,
+}
+
+impl TokenBudget {
+    /// Creates a new [`TokenBudget`] policy.
+    #[inline]
+    pub const fn new(max_tokens: usize) -> Self {
+        Self {
+            max_tokens,
+            tokenizer: Tokenizer::Heuristic,
+        }
+    }
+
+    /// Overrides the default [`Tokenizer::Heuristic`].
+    #[inline]
+    pub const fn with_tokenizer(mut self, tokenizer: Tokenizer) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+}
+
+impl TruncationPolicy for TokenBudget {
+    fn truncate_pinned(
+        &self,
+        request: &mut ChatRequest,
+        pinned: &[ChatMessage],
+    ) -> Vec<ChatMessage> {
+        let Some(history) = request.message_history.as_mut() else {
+            return Vec::new();
+        };
+
+        let count = |x: &ChatMessage| self.tokenizer.count_message(x);
+        let reserved: usize = pinned.iter().chain(Some(&request.message)).map(count).sum();
+
+        let mut total: usize = reserved + history.iter().map(count).sum::<usize>();
+        let mut split = 0;
+        while total > self.max_tokens && split < history.len() {
+            total -= count(&history[split]);
+            split += 1;
+        }
+
+        let split = skip_results(history, split);
+        history.drain(..split).collect()
+    }
+}
+
+/// Always keeps [`Role::System`] messages in their positions,
+/// truncating the rest with the inner policy.
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepSystem<P>(pub P);
+
+impl<P: TruncationPolicy> TruncationPolicy for KeepSystem<P> {
+    fn truncate_pinned(
+        &self,
+        request: &mut ChatRequest,
+        pinned: &[ChatMessage],
+    ) -> Vec<ChatMessage> {
+        let Some(history) = request.message_history.take() else {
+            return Vec::new();
+        };
+
+        let (system, rest): (Vec<_>, Vec<_>) = history
+            .iter()
+            .cloned()
+            .partition(|x| x.role() == Role::System);
+
+        request.message_history = Some(rest);
+        let pinned: Vec<_> = pinned.iter().chain(system.iter()).cloned().collect();
+        let dropped = self.0.truncate_pinned(request, &pinned);
+
+        // Kept messages are matched from the end, as policies mostly drop the oldest ones.
+        let mut kept = request.message_history.take().unwrap_or_default();
+        let mut merged = Vec::with_capacity(kept.len() + system.len());
+        for message in history.into_iter().rev() {
+            if message.role() == Role::System {
+                merged.push(message);
+            } else if kept.last() == Some(&message) {
+                merged.extend(kept.pop());
+            }
+        }
+
+        merged.extend(kept.into_iter().rev());
+        merged.reverse();
+        request.message_history = Some(merged);
+
+        dropped
+    }
+}
+
+/// Returns the index of the first message from `index` that is not a tool result,
+/// so that tool results are never split from their tool call.
+fn skip_results(history: &[ChatMessage], mut index: usize) -> usize {
+    while history.get(index).is_some_and(|x| x.role() == Role::Tool) {
+        index += 1;
+    }
+
+    index
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lang::chat::{ChatMessage, ChatRequest, Role};
+    use crate::lang::truncate::{DropMiddle, KeepLast, KeepSystem, TokenBudget, TruncationPolicy};
+
+    fn request(history: &[&str]) -> ChatRequest {
+        let mut request = ChatRequest::new("Hello!");
+        let history = history.iter().map(|x| ChatMessage::new(x)).collect();
+        request.message_history = Some(history);
+        request
+    }
+
+    #[test]
+    fn keep_last() {
+        let mut request = request(&["1", "2", "3"]);
+        let dropped = KeepLast(2).truncate(&mut request);
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(request.message_history.unwrap()[0].content, "2");
+    }
+
+    #[test]
+    fn drop_middle() {
+        let mut request = request(&["1", "2", "3", "4", "5"]);
+        let dropped = DropMiddle::new(1, 2).truncate(&mut request);
+        assert_eq!(dropped.len(), 2);
+
+        let history = request.message_history.unwrap();
+        let history: Vec<_> = history
+            .iter()
+            .map(|x| x.content.as_text().unwrap())
+            .collect();
+        assert_eq!(history, ["1", "4", "5"]);
+    }
+
+    #[test]
+    fn tool_results() {
+        let mut request = request(&["1", "call", "result", "4", "5"]);
+        let history = request.message_history.as_mut().unwrap();
+        history[2] = ChatMessage::new("result").with_role(Role::Tool);
+
+        // The result of the dropped call is dropped too.
+        assert_eq!(KeepLast(3).truncate(&mut request.clone()).len(), 3);
+        assert_eq!(
+            DropMiddle::new(1, 2).truncate(&mut request.clone()).len(),
+            2
+        );
+
+        // The result of the kept call is kept too.
+        let dropped = DropMiddle::new(2, 1).truncate(&mut request);
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].content, "4");
+    }
+
+    #[test]
+    fn keep_system() {
+        let mut request = request(&["1", "2", "3"]);
+        let history = request.message_history.as_mut().unwrap();
+        history.insert(0, ChatMessage::new("Be concise.").with_system());
+
+        let dropped = KeepSystem(TokenBudget::new(14)).truncate(&mut request);
+        assert_eq!(dropped.len(), 3);
+
+        let history = request.message_history.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].content, "Be concise.");
+    }
+
+    #[test]
+    fn keep_system_position() {
+        let mut request = request(&["1", "2", "3"]);
+        let history = request.message_history.as_mut().unwrap();
+        history.insert(2, ChatMessage::new("Be concise.").with_system());
+
+        let dropped = KeepSystem(KeepLast(2)).truncate(&mut request);
+        assert_eq!(dropped.len(), 1);
+
+        let history = request.message_history.unwrap();
+        let history: Vec<_> = history
+            .iter()
+            .map(|x| x.content.as_text().unwrap())
+            .collect();
+        assert_eq!(history, ["2", "Be concise.", "3"]);
+    }
+}
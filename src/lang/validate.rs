@@ -0,0 +1,386 @@
+//! Local validation of [`RouterConfigs`], e.g. before a gateway rollout.
+//!
+//! Locations of [`Diagnostic`]s are `JSON` pointers into the serialized
+//! [`RouterConfigs`], e.g. `/routers/0/models/1/weight`.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::lang::list::{ModelConfig, RouterConfig, RouterConfigs, RoutingStrategy};
+
+/// Providers that do not require credentials.
+const KEYLESS_PROVIDERS: [&str; 1] = ["ollama"];
+/// Credential fields of providers, `api_key` unless listed.
+const CREDENTIALS: [(&str, &[&str]); 1] =
+    [("bedrock", &["aws_access_key_id", "aws_secret_access_key"])];
+
+/// Severity of the [`Diagnostic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The configuration works, but likely not as intended.
+    Warning,
+    /// The configuration is rejected or broken.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Warning => f.write_str("warning"),
+            Self::Error => f.write_str("error"),
+        }
+    }
+}
+
+/// Kind of the [`Diagnostic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticKind {
+    /// Multiple routers with the same id.
+    DuplicateRouter,
+    /// Multiple models with the same id in the router.
+    DuplicateModel,
+    /// Model weight is negative.
+    InvalidWeight,
+    /// Model never receives requests, e.g. is disabled
+    /// or has a zero weight under [`RoutingStrategy::WeightedRoundRobin`].
+    UnreachableModel,
+    /// Model has no provider or more than one.
+    InvalidProvider,
+    /// Provider credentials are missing or empty.
+    MissingCredentials,
+    /// Provider credentials are not an environment placeholder, e.g. `${env:OPENAI_API_KEY}`.
+    HardcodedCredentials,
+    /// Routing strategy is not recognized.
+    UnknownStrategy,
+}
+
+/// Single issue found by the [`Validator`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub kind: DiagnosticKind,
+    /// `JSON` pointer to the offending value.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}: {}", self.severity, self.path, self.message)
+    }
+}
+
+/// [`RouterConfigs`] validator.
+///
+/// #### Example
+///
+/// ```rust,no_run
+/// use glide_rs::lang::list::RouterConfigs;
+/// use glide_rs::lang::validate::{Severity, Validator};
+///
+/// # let _ = || {
+/// let configs = RouterConfigs::from_file("routers.json")?;
+/// let diagnostics = Validator::new().with_credentials(true).validate(&configs);
+/// for diagnostic in &diagnostics {
+///     println!("{diagnostic}");
+/// }
+///
+/// assert!(diagnostics.iter().all(|x| x.severity < Severity::Error));
+/// # Ok::<_, glide_rs::Error>(())
+/// # };
+/// ```
+#[must_use]
+#[derive(Debug, Default, Clone)]
+pub struct Validator {
+    credentials: bool,
+}
+
+impl Validator {
+    /// Creates a new [`Validator`].
+    #[inline]
+    pub const fn new() -> Self {
+        Self { credentials: false }
+    }
+
+    /// Enables checks of provider credentials.
+    ///
+    /// Only meaningful for configurations loaded from files, as
+    /// [`Language::list`] never returns credentials.
+    ///
+    /// Default value: `false`
+    ///
+    /// [`Language::list`]: crate::lang::Language::list
+    pub const fn with_credentials(mut self, enabled: bool) -> Self {
+        self.credentials = enabled;
+        self
+    }
+
+    /// Returns all [`Diagnostic`]s of the [`RouterConfigs`].
+    #[must_use]
+    pub fn validate(&self, configs: &RouterConfigs) -> Vec<Diagnostic> {
+        let mut diagnostics = Diagnostics::default();
+        let mut routers = HashSet::new();
+
+        for (i, router) in configs.routers.iter().enumerate() {
+            let path = format!("/routers/{i}");
+            if !routers.insert(router.id.as_str()) {
+                diagnostics.push(
+                    Severity::Error,
+                    DiagnosticKind::DuplicateRouter,
+                    format!("{path}/routerID"),
+                    format!("router `{}` is defined more than once", router.id),
+                );
+            }
+
+            self.validate_router(&mut diagnostics, &path, router);
+        }
+
+        diagnostics.0
+    }
+
+    fn validate_router(&self, diagnostics: &mut Diagnostics, path: &str, router: &RouterConfig) {
+        if let RoutingStrategy::Unknown(strategy) = &router.strategy {
+            diagnostics.push(
+                Severity::Error,
+                DiagnosticKind::UnknownStrategy,
+                format!("{path}/strategy"),
+                format!("unknown routing strategy `{strategy}`"),
+            );
+        }
+
+        let weighted = router.strategy == RoutingStrategy::WeightedRoundRobin;
+        let reachable = |x: &ModelConfig| x.enabled && !(weighted && x.weight == Some(0));
+        if router.enabled && !router.models.iter().any(reachable) {
+            diagnostics.push(
+                Severity::Error,
+                DiagnosticKind::UnreachableModel,
+                format!("{path}/models"),
+                format!("router `{}` has no reachable models", router.id),
+            );
+        }
+
+        let mut models = HashSet::new();
+        for (j, model) in router.models.iter().enumerate() {
+            let path = format!("{path}/models/{j}");
+            if !models.insert(model.id.as_str()) {
+                diagnostics.push(
+                    Severity::Error,
+                    DiagnosticKind::DuplicateModel,
+                    format!("{path}/id"),
+                    format!("model `{}` is defined more than once", model.id),
+                );
+            }
+
+            self.validate_model(diagnostics, &path, model, weighted);
+        }
+    }
+
+    fn validate_model(
+        &self,
+        diagnostics: &mut Diagnostics,
+        path: &str,
+        model: &ModelConfig,
+        weighted: bool,
+    ) {
+        match model.weight {
+            Some(weight) if weight < 0 => diagnostics.push(
+                Severity::Error,
+                DiagnosticKind::InvalidWeight,
+                format!("{path}/weight"),
+                format!("model `{}` has a negative weight {weight}", model.id),
+            ),
+            Some(0) if weighted && model.enabled => diagnostics.push(
+                Severity::Warning,
+                DiagnosticKind::UnreachableModel,
+                format!("{path}/weight"),
+                format!("model `{}` has a zero weight", model.id),
+            ),
+            _ => {}
+        }
+
+        if !model.enabled {
+            diagnostics.push(
+                Severity::Warning,
+                DiagnosticKind::UnreachableModel,
+                format!("{path}/enabled"),
+                format!("model `{}` is disabled", model.id),
+            );
+        }
+
+        let providers = model.providers();
+        if providers.len() != 1 {
+            let message = match providers.as_slice() {
+                [] => format!("model `{}` has no provider", model.id),
+                x => format!(
+                    "model `{}` has multiple providers: {}",
+                    model.id,
+                    x.join(", ")
+                ),
+            };
+
+            diagnostics.push(
+                Severity::Error,
+                DiagnosticKind::InvalidProvider,
+                path.to_owned(),
+                message,
+            );
+        }
+
+        if !self.credentials {
+            return;
+        }
+
+        for provider in providers {
+            if KEYLESS_PROVIDERS.contains(&provider) {
+                continue;
+            }
+
+            let fields = CREDENTIALS.iter().find(|(x, _)| *x == provider);
+            let fields = fields.map_or(&["api_key"][..], |(_, x)| *x);
+            let extra = provider_extra(model, provider);
+            for field in fields {
+                let path = format!("{path}/{provider}/{field}");
+                validate_credential(diagnostics, path, extra.and_then(|x| x.get(*field)));
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+    fn push(&mut self, severity: Severity, kind: DiagnosticKind, path: String, message: String) {
+        self.0.push(Diagnostic {
+            severity,
+            kind,
+            path,
+            message,
+        });
+    }
+}
+
+/// Returns the unrecognized fields of the provider config, where credentials are kept.
+fn provider_extra<'a>(model: &'a ModelConfig, provider: &str) -> Option<&'a Map<String, Value>> {
+    match provider {
+        "openai" => model.openai.as_ref().map(|x| &x.extra),
+        "azureopenai" => model.azureopenai.as_ref().map(|x| &x.extra),
+        "anthropic" => model.anthropic.as_ref().map(|x| &x.extra),
+        "cohere" => model.cohere.as_ref().map(|x| &x.extra),
+        "bedrock" => model.bedrock.as_ref().map(|x| &x.extra),
+        "ollama" => model.ollama.as_ref().map(|x| &x.extra),
+        "octoml" => model.octoml.as_ref().map(|x| &x.extra),
+        _ => None,
+    }
+}
+
+fn validate_credential(diagnostics: &mut Diagnostics, path: String, value: Option<&Value>) {
+    let value = value.and_then(Value::as_str).map(str::trim);
+    match value {
+        None | Some("") => diagnostics.push(
+            Severity::Error,
+            DiagnosticKind::MissingCredentials,
+            path,
+            "credentials are missing".to_owned(),
+        ),
+        Some(x) if !(x.starts_with("${") && x.ends_with('}')) => diagnostics.push(
+            Severity::Warning,
+            DiagnosticKind::HardcodedCredentials,
+            path,
+            "credentials are not an environment placeholder".to_owned(),
+        ),
+        Some(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::lang::list::RouterConfigs;
+    use crate::lang::validate::{DiagnosticKind, Severity, Validator};
+
+    #[test]
+    fn validate() {
+        let configs: RouterConfigs = serde_json::from_value(json!({
+            "routers": [
+                {
+                    "routerID": "a",
+                    "enabled": true,
+                    "strategy": "random",
+                    "models": [
+                        { "id": "m", "enabled": true, "weight": -1, "openai": { "api_key": "sk-1" } },
+                        { "id": "m", "enabled": false, "cohere": {}, "anthropic": {} },
+                        { "id": "n", "enabled": true, "ollama": {} },
+                    ],
+                },
+                {
+                    "routerID": "a",
+                    "enabled": true,
+                    "models": [
+                        { "id": "m", "enabled": false, "openai": { "api_key": "${env:OPENAI_API_KEY}" } },
+                    ],
+                },
+                {
+                    "routerID": "b",
+                    "enabled": true,
+                    "strategy": "weighted_round_robin",
+                    "models": [
+                        { "id": "m", "enabled": true, "weight": 0, "ollama": {} },
+                        { "id": "n", "enabled": true, "weight": 1, "ollama": {} },
+                    ],
+                },
+            ],
+        }))
+        .unwrap();
+
+        let diagnostics = configs.validate();
+        let kinds: Vec<_> = diagnostics
+            .iter()
+            .map(|x| (x.kind, x.path.as_str()))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (DiagnosticKind::UnknownStrategy, "/routers/0/strategy"),
+                (DiagnosticKind::InvalidWeight, "/routers/0/models/0/weight"),
+                (DiagnosticKind::DuplicateModel, "/routers/0/models/1/id"),
+                (
+                    DiagnosticKind::UnreachableModel,
+                    "/routers/0/models/1/enabled"
+                ),
+                (DiagnosticKind::InvalidProvider, "/routers/0/models/1"),
+                (DiagnosticKind::DuplicateRouter, "/routers/1/routerID"),
+                (DiagnosticKind::UnreachableModel, "/routers/1/models"),
+                (
+                    DiagnosticKind::UnreachableModel,
+                    "/routers/1/models/0/enabled"
+                ),
+                (
+                    DiagnosticKind::UnreachableModel,
+                    "/routers/2/models/0/weight"
+                ),
+            ]
+        );
+
+        let diagnostics = Validator::new().with_credentials(true).validate(&configs);
+        let credentials: Vec<_> = diagnostics
+            .iter()
+            .filter(|x| x.path.ends_with("api_key"))
+            .map(|x| (x.severity, x.path.as_str()))
+            .collect();
+        assert_eq!(
+            credentials,
+            [
+                (Severity::Warning, "/routers/0/models/0/openai/api_key"),
+                (Severity::Error, "/routers/0/models/1/anthropic/api_key"),
+                (Severity::Error, "/routers/0/models/1/cohere/api_key"),
+            ]
+        );
+    }
+}
@@ -9,14 +9,26 @@ pub(crate) use config::Config;
 mod builder;
 mod client;
 mod config;
+mod duration;
 mod error;
+#[cfg(test)]
+mod fixtures;
+mod gateway;
+mod health;
 pub mod lang;
+mod timeout;
 
 pub mod types {
     //! Request and response types.
     //!
 
     pub use super::error::{ErrorKind, ErrorResponse};
+    pub use super::gateway::{Capability, Unsupported, Version};
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+    pub use super::health::HealthWatch;
+    pub use super::health::{HealthReport, HealthState, HealthTransition};
+    pub use super::timeout::{Timeout, TimeoutKind};
 }
 
 /// Error type for a [`Client`].
@@ -31,14 +43,65 @@ pub enum Error {
     #[cfg(feature = "streaming")]
     #[cfg_attr(docsrs, doc(cfg(feature = "streaming")))]
     #[error("websocket error: {0}")]
-    Ws(#[from] reqwest_websocket::Error),
+    Ws(Box<reqwest_websocket::Error>),
+
+    /// Errors that may occur during the decoding of a streaming chat message.
+    #[cfg(feature = "streaming")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "streaming")))]
+    #[error("decode error: {0}")]
+    Decode(#[from] lang::chat_stream::DecodeError),
+
+    /// Errors that may occur during the processing of a file.
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
 
     /// Errors that may occur during the processing of an API request.
     #[error("api error: {0}")]
     Api(#[from] types::ErrorResponse),
+
+    /// Errors that may occur during the parsing of a structured output.
+    #[error("structured output error: {0}")]
+    Structured(#[from] lang::structured::StructuredError),
+
+    /// Errors that may occur during the parsing or rendering of a prompt template.
+    #[error("template error: {0}")]
+    Template(#[from] lang::template::TemplateError),
+
+    /// The model still requested tool calls after the maximum number of steps.
+    #[error("tool error: exceeded the limit of {0} steps")]
+    ToolStepLimit(usize),
+
+    /// The request would exceed the spending limit of a [`Budget`].
+    ///
+    /// [`Budget`]: lang::budget::Budget
+    #[error("budget error: {0}")]
+    BudgetExceeded(#[from] lang::budget::BudgetExceeded),
+
+    /// The `router` is not in the `RouterCache`.
+    #[error("router error: {0}")]
+    UnknownRouter(#[from] lang::discovery::UnknownRouter),
+
+    /// The connected gateway does not support the requested feature.
+    #[error("gateway error: {0}")]
+    Unsupported(#[from] types::Unsupported),
+
+    /// The chat request or the streaming chat connection stopped responding.
+    #[error("timeout error: {0}")]
+    Timeout(#[from] types::Timeout),
 }
 
 /// Specialized [`Result`] type for an [`Error`].
 ///
 /// [`Result`]: std::result::Result
 pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[cfg(feature = "streaming")]
+impl From<reqwest_websocket::Error> for Error {
+    #[inline]
+    fn from(value: reqwest_websocket::Error) -> Self {
+        match value {
+            reqwest_websocket::Error::Json(x) => lang::chat_stream::DecodeError::new(x).into(),
+            x => Self::Ws(Box::new(x)),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use std::fmt;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Kind of the [`Timeout`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeoutKind {
+    /// No response was received to the chat request.
+    Request,
+    /// No frames were received, including `pong`s.
+    Idle,
+    /// No messages were received while a response was incomplete.
+    Chunk,
+}
+
+/// Chat request or streaming chat connection that stopped responding.
+///
+/// Streams end after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("{} for {after:?}", Kind(*kind))]
+pub struct Timeout {
+    pub kind: TimeoutKind,
+    pub after: Duration,
+}
+
+struct Kind(TimeoutKind);
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            TimeoutKind::Request => f.write_str("no response was received"),
+            TimeoutKind::Idle => f.write_str("connection was idle"),
+            TimeoutKind::Chunk => f.write_str("no chunks were received"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::types::{Timeout, TimeoutKind};
+
+    #[test]
+    fn timeout() {
+        let timeout = Timeout {
+            kind: TimeoutKind::Chunk,
+            after: Duration::from_secs(10),
+        };
+
+        let error = crate::Error::from(timeout);
+        assert_eq!(
+            error.to_string(),
+            "timeout error: no chunks were received for 10s"
+        );
+    }
+}